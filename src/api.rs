@@ -1,14 +1,23 @@
+use crate::retry::RetryBudget;
 use anyhow::{anyhow, Context, Result};
-use reqwest::header::AUTHORIZATION;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, PROXY_AUTHORIZATION, RETRY_AFTER,
+};
+use reqwest::{Client, Method, Request, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
-use std::sync::Mutex;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 pub const API_BASE_URL: &str = "https://cloud.lambdalabs.com/api/v1";
-const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Timeout for each API request unless a call overrides it
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Shortest interval between `find` availability polls, unless `--allow-fast-polling` is passed
+pub const POLL_INTERVAL_FLOOR: Duration = Duration::from_secs(5);
+/// Maximum length of a response body quoted in an error message
+const BODY_SNIPPET_LEN: usize = 200;
 
 #[derive(Error, Debug)]
 pub enum LambdaError {
@@ -20,12 +29,113 @@ pub enum LambdaError {
     InstanceTypeNotFound(String),
     #[error("No regions available for instance type '{0}'")]
     NoRegionsAvailable(String),
+    #[error("Region '{region}' is not available for instance type '{gpu}'. Available regions: {available}")]
+    RegionNotAvailable {
+        region: String,
+        gpu: String,
+        available: String,
+    },
     #[error("No instance IDs returned from launch request")]
     NoInstanceIds,
     #[error("API request failed: {0}")]
     ApiError(String),
     #[error("SSH key is required for this operation")]
     SshKeyRequired,
+    #[error("API key was rejected (401 Unauthorized). Check that LAMBDA_API_KEY or LAMBDA_API_KEY_COMMAND provides a valid, unexpired key")]
+    Unauthorized,
+    #[error(
+        "API key lacks permission to {0} (403 Forbidden). Use a key with access to this operation"
+    )]
+    Forbidden(String),
+    #[error("Your Lambda account is inactive or suspended ({status}): {message}. Retrying won't help; check billing and account status at https://cloud.lambda.ai or contact Lambda support")]
+    AccountSuspended { status: StatusCode, message: String },
+    #[error("Quota exceeded trying to {action} ({status}): {message}. Retrying won't help; terminate instances you don't need or ask Lambda support to raise the quota")]
+    QuotaExceeded {
+        action: String,
+        status: StatusCode,
+        message: String,
+    },
+    #[error("Filesystem '{0}' not found. Available filesystems: {1}")]
+    FilesystemNotFound(String, String),
+    #[error("Filesystem '{name}' is in region '{filesystem_region}', but the instance would launch in '{region}'")]
+    FilesystemRegionMismatch {
+        name: String,
+        filesystem_region: String,
+        region: String,
+    },
+    #[error("An instance name is required (set by LAMBDA_REQUIRE_NAME or --name-required)")]
+    NameRequired,
+    #[error("Instance {instance_id} launched in {region} but isn't ready: {reason}")]
+    LaunchedNotReady {
+        instance_id: String,
+        region: String,
+        reason: String,
+    },
+    #[error("Failed to {action} ({status}): {message}")]
+    ApiStatus {
+        action: String,
+        status: StatusCode,
+        /// The API's error code (e.g. `global/invalid-parameters`), if it sent one
+        code: Option<String>,
+        message: String,
+    },
+}
+
+impl LambdaError {
+    /// Whether the failure was a lack of capacity for the instance type.
+    ///
+    /// Covers both the local region check and the API rejecting a launch. The
+    /// API's error code decides when there is one; only an uncoded 400 falls
+    /// back to its message ("Not enough capacity to fulfill launch request").
+    pub fn is_no_capacity(&self) -> bool {
+        match self {
+            Self::NoRegionsAvailable(_) | Self::RegionNotAvailable { .. } => true,
+            Self::ApiStatus {
+                code: Some(code), ..
+            } => code == "instance-operations/launch/insufficient-capacity",
+            Self::ApiStatus {
+                status,
+                code: None,
+                message,
+                ..
+            } => *status == StatusCode::BAD_REQUEST && message.to_lowercase().contains("capacity"),
+            _ => false,
+        }
+    }
+
+    /// Stable identifier for this error, for scripts that match on failures
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::ApiKeyNotSet => "api_key_not_set",
+            Self::ApiKeyCommandFailed(_) => "api_key_command_failed",
+            Self::InstanceTypeNotFound(_) => "instance_type_not_found",
+            Self::NoRegionsAvailable(_) => "no_regions_available",
+            Self::RegionNotAvailable { .. } => "region_not_available",
+            Self::NoInstanceIds => "no_instance_ids",
+            Self::ApiError(_) | Self::ApiStatus { .. } => "api_error",
+            Self::SshKeyRequired => "ssh_key_required",
+            Self::Unauthorized => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::AccountSuspended { .. } => "account_suspended",
+            Self::QuotaExceeded { .. } => "quota_exceeded",
+            Self::FilesystemNotFound(..) => "filesystem_not_found",
+            Self::FilesystemRegionMismatch { .. } => "filesystem_region_mismatch",
+            Self::NameRequired => "name_required",
+            Self::LaunchedNotReady { .. } => "launched_not_ready",
+        }
+    }
+
+    /// HTTP status of the failed API request, if there was one
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            Self::Unauthorized => Some(StatusCode::UNAUTHORIZED.as_u16()),
+            Self::Forbidden(_) => Some(StatusCode::FORBIDDEN.as_u16()),
+            Self::AccountSuspended { status, .. }
+            | Self::QuotaExceeded { status, .. }
+            | Self::ApiStatus { status, .. } => Some(status.as_u16()),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -40,18 +150,102 @@ pub struct ApiErrorResponse {
 
 #[derive(Deserialize, Debug)]
 pub struct ApiErrorDetail {
+    /// Machine-readable code, e.g. `global/quota-exceeded`
+    #[serde(default)]
+    pub code: Option<String>,
     pub message: String,
 }
 
+/// Lifecycle state of an instance, as reported by the API
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum InstanceStatus {
+    Active,
+    Booting,
+    Unhealthy,
+    Terminated,
+    /// A status this client doesn't know about, kept verbatim
+    Unknown(String),
+}
+
+impl InstanceStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Active => "active",
+            Self::Booting => "booting",
+            Self::Unhealthy => "unhealthy",
+            Self::Terminated => "terminated",
+            Self::Unknown(status) => status,
+        }
+    }
+
+    /// Whether the instance can no longer become ready
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Self::Unhealthy | Self::Terminated)
+    }
+}
+
+impl From<String> for InstanceStatus {
+    fn from(status: String) -> Self {
+        match status.as_str() {
+            "active" => Self::Active,
+            "booting" => Self::Booting,
+            "unhealthy" => Self::Unhealthy,
+            "terminated" => Self::Terminated,
+            _ => Self::Unknown(status),
+        }
+    }
+}
+
+impl From<InstanceStatus> for String {
+    fn from(status: InstanceStatus) -> Self {
+        status.as_str().to_string()
+    }
+}
+
+impl std::fmt::Display for InstanceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Deserialize, Debug, Clone, Serialize)]
 pub struct Instance {
     pub id: Option<String>,
     pub name: Option<String>,
-    pub status: Option<String>,
+    pub status: Option<InstanceStatus>,
+    /// Why the instance is in its current status (e.g. unhealthy), if the API reports it
+    #[serde(default, alias = "status_detail")]
+    pub status_reason: Option<String>,
     pub ip: Option<String>,
     pub ssh_key_names: Option<Vec<String>>,
+    /// Names of filesystems attached to the instance
+    #[serde(default)]
+    pub file_system_names: Option<Vec<String>>,
     pub instance_type: Option<InstanceTypeInfo>,
     pub region: Option<RegionInfo>,
+    /// JupyterLab URL, once the API reports one
+    #[serde(default)]
+    pub jupyter_url: Option<String>,
+}
+
+impl Instance {
+    /// Instance type name, e.g. `gpu_1x_a10`
+    pub fn type_name(&self) -> Option<&str> {
+        self.instance_type.as_ref()?.name.as_deref()
+    }
+
+    /// Status with the reported reason appended, e.g. `unhealthy (disk failure)`
+    pub fn status_display(&self) -> String {
+        let status = self
+            .status
+            .as_ref()
+            .map_or("unknown", InstanceStatus::as_str);
+        match self.status_reason.as_deref() {
+            Some(reason) if !reason.is_empty() => format!("{} ({})", status, reason),
+            _ => status.to_string(),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -59,9 +253,12 @@ pub struct InstanceTypeInfo {
     pub name: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Clone, Serialize)]
+#[derive(Deserialize, Debug, Clone, Default, Serialize)]
 pub struct RegionInfo {
     pub name: Option<String>,
+    /// Human-readable name, e.g. "US East (Virginia)"
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
 /// Filesystem (persistent storage) information
@@ -88,6 +285,12 @@ pub struct LaunchResponse {
     pub instance_ids: Vec<String>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct TerminateResponse {
+    #[serde(default)]
+    pub terminated_instances: Vec<Instance>,
+}
+
 #[derive(Deserialize, Debug, Clone, Serialize)]
 pub struct InstanceTypeData {
     pub name: String,
@@ -108,10 +311,29 @@ pub struct InstanceTypeResponse {
 #[derive(Deserialize, Debug, Clone)]
 pub struct InstanceType {
     pub description: String,
+    #[serde(deserialize_with = "int_or_string")]
     pub price_cents_per_hour: i32,
     pub specs: InstanceSpecs,
 }
 
+/// Accept an integer sent either as a JSON number or as a string (e.g. `"75"`)
+fn int_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<i32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum IntOrString {
+        Int(i32),
+        String(String),
+    }
+
+    match IntOrString::deserialize(deserializer)? {
+        IntOrString::Int(n) => Ok(n),
+        IntOrString::String(s) => s
+            .trim()
+            .parse()
+            .map_err(|_| serde::de::Error::custom(format!("invalid integer '{}'", s))),
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct InstanceSpecs {
     pub vcpus: u32,
@@ -133,14 +355,57 @@ enum ApiKeySource {
     Direct(String),
     /// Command to execute to get the API key (lazy evaluation)
     Command(String),
+    /// Nothing configured; every request fails with `ApiKeyNotSet`
+    Missing,
+}
+
+/// A header whose value is the output of a command (e.g. a gateway token)
+struct CommandHeader {
+    name: HeaderName,
+    command: String,
+    /// How long a value is reused before the command runs again (forever if unset)
+    ttl: Option<Duration>,
+    cached: Mutex<Option<(HeaderValue, Instant)>>,
+}
+
+impl CommandHeader {
+    fn value(&self) -> Result<HeaderValue> {
+        let mut cache = self
+            .cached
+            .lock()
+            .map_err(|e| anyhow!("Failed to acquire lock: {}", e))?;
+
+        if let Some((value, fetched)) = cache.as_ref() {
+            if self.ttl.is_none_or(|ttl| fetched.elapsed() < ttl) {
+                return Ok(value.clone());
+            }
+        }
+
+        let output = execute_command(&self.command)
+            .map_err(|e| anyhow!("Failed to get value for header '{}': {}", self.name, e))?;
+        let value = HeaderValue::from_str(&output)
+            .with_context(|| format!("Command output is not a valid '{}' header", self.name))?;
+        *cache = Some((value.clone(), Instant::now()));
+        Ok(value)
+    }
 }
 
 /// Lambda API client
 pub struct LambdaClient {
     client: Client,
     api_key_source: ApiKeySource,
-    /// Cached API key (used for lazy evaluation)
-    cached_api_key: Mutex<Option<String>>,
+    /// Cached API key from the command, and when it was fetched
+    cached_api_key: Arc<Mutex<Option<(String, Instant)>>>,
+    /// How long a command-provided key is reused before the command runs again
+    api_key_ttl: Option<Duration>,
+    /// Headers added to every request in addition to `Authorization`
+    extra_headers: HeaderMap,
+    /// Retries shared by all requests made through this client (not its `for_operation` views)
+    retry_budget: RetryBudget,
+    /// Header computed by a command and sent with every request
+    command_header: Option<Arc<CommandHeader>>,
+    /// Refuse to launch instances without a name
+    require_name: bool,
 }
 
 impl LambdaClient {
@@ -154,7 +419,12 @@ impl LambdaClient {
         Ok(Self {
             client,
             api_key_source: ApiKeySource::Direct(api_key),
-            cached_api_key: Mutex::new(None),
+            cached_api_key: Arc::default(),
+            api_key_ttl: None,
+            extra_headers: HeaderMap::new(),
+            retry_budget: RetryBudget::default(),
+            command_header: None,
+            require_name: false,
         })
     }
 
@@ -169,7 +439,12 @@ impl LambdaClient {
         Ok(Self {
             client,
             api_key_source,
-            cached_api_key: Mutex::new(None),
+            cached_api_key: Arc::default(),
+            api_key_ttl: None,
+            extra_headers: HeaderMap::new(),
+            retry_budget: RetryBudget::default(),
+            command_header: None,
+            require_name: false,
         })
     }
 
@@ -187,102 +462,281 @@ impl LambdaClient {
     /// Create a client using environment variables for the API key with options.
     ///
     /// If `lazy` is true and `LAMBDA_API_KEY_COMMAND` is used, the command execution
-    /// is deferred until the first API request.
+    /// is deferred until the first API request. A lazy client also starts without
+    /// any key configured, and each request then fails with `ApiKeyNotSet`.
+    ///
+    /// Extra headers from `LAMBDA_EXTRA_HEADERS` (comma-separated `name=value`) are
+    /// applied to every request. If `LAMBDA_API_KEY_TTL` is set (seconds), a
+    /// command-provided key is re-fetched once it is older than that.
     pub fn from_env_with_options(lazy: bool) -> Result<Self> {
+        Self::from_env_key(lazy)?
+            .with_env_api_key_ttl()?
+            .with_env_headers()?
+            .with_env_proxy_auth()?
+            .with_env_retry_budget()
+            .map(Self::with_env_require_name)
+    }
+
+    fn from_env_key(lazy: bool) -> Result<Self> {
         // First, try direct API key (always immediate)
         if let Ok(key) = std::env::var("LAMBDA_API_KEY") {
             if !key.is_empty() {
-                return Self::new(key);
+                return Self::new(resolve_env_reference("LAMBDA_API_KEY", key)?);
             }
         }
 
         // Then, try command-based retrieval
         if let Ok(command) = std::env::var("LAMBDA_API_KEY_COMMAND") {
             if !command.is_empty() {
-                if lazy {
-                    // Defer command execution until first API request
-                    return Self::new_lazy(ApiKeySource::Command(command));
-                } else {
+                let command = resolve_env_reference("LAMBDA_API_KEY_COMMAND", command)?;
+                let client = Self::new_lazy(ApiKeySource::Command(command))?;
+                if !lazy {
                     // Execute command immediately (default behavior)
-                    let key = execute_api_key_command(&command)?;
-                    return Self::new(key);
+                    client.get_api_key()?;
                 }
+                // Either way the command is kept, so the key can be refreshed later
+                return Ok(client);
             }
         }
 
+        if lazy {
+            return Self::new_lazy(ApiKeySource::Missing);
+        }
         Err(LambdaError::ApiKeyNotSet.into())
     }
 
+    /// Create a client with an API key read from stdin (first line, trimmed).
+    ///
+    /// Takes precedence over the environment; useful when the key shouldn't be
+    /// placed in an env var or file.
+    pub fn from_stdin() -> Result<Self> {
+        let key = read_api_key(std::io::stdin().lock())?;
+        Self::new(key)?
+            .with_env_headers()?
+            .with_env_proxy_auth()?
+            .with_env_retry_budget()
+            .map(Self::with_env_require_name)
+    }
+
+    /// Send requests through `client` instead of the client's own.
+    ///
+    /// Lets a long-lived process share one connection pool (e.g. with a
+    /// [`Notifier`](crate::notify::Notifier)). The injected client's timeouts apply.
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// The underlying HTTP client, for sharing its connection pool
+    pub fn http_client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Add a header sent with every request (e.g. for a proxy or tracing)
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .with_context(|| format!("Invalid header name '{}'", name))?;
+        let value = HeaderValue::from_str(value.trim())
+            .with_context(|| format!("Invalid value for header '{}'", name))?;
+        self.extra_headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// Apply headers from `LAMBDA_EXTRA_HEADERS`, if set
+    fn with_env_headers(self) -> Result<Self> {
+        let Ok(headers) = std::env::var("LAMBDA_EXTRA_HEADERS") else {
+            return Ok(self);
+        };
+
+        parse_header_list(&headers)?
+            .into_iter()
+            .try_fold(self, |client, (name, value)| {
+                client.with_header(name, value)
+            })
+    }
+
+    /// Send a header whose value is the output of `command` with every request.
+    ///
+    /// The command runs on first use; its output is reused until `ttl` passes
+    /// (or for the life of the client if `ttl` is `None`).
+    pub fn with_header_command(
+        mut self,
+        name: &str,
+        command: &str,
+        ttl: Option<Duration>,
+    ) -> Result<Self> {
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .with_context(|| format!("Invalid header name '{}'", name))?;
+        self.command_header = Some(Arc::new(CommandHeader {
+            name,
+            command: command.to_string(),
+            ttl,
+            cached: Mutex::new(None),
+        }));
+        Ok(self)
+    }
+
+    /// Apply `LAMBDA_PROXY_AUTH_COMMAND`, sent as `LAMBDA_PROXY_AUTH_HEADER`
+    /// (default `Proxy-Authorization`) and re-run after `LAMBDA_PROXY_AUTH_TTL` seconds
+    fn with_env_proxy_auth(self) -> Result<Self> {
+        let command = match std::env::var("LAMBDA_PROXY_AUTH_COMMAND") {
+            Ok(command) if !command.is_empty() => command,
+            _ => return Ok(self),
+        };
+        let name = std::env::var("LAMBDA_PROXY_AUTH_HEADER")
+            .ok()
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| PROXY_AUTHORIZATION.to_string());
+        let ttl = match std::env::var("LAMBDA_PROXY_AUTH_TTL") {
+            Ok(secs) if !secs.is_empty() => {
+                let secs: u64 = secs.trim().parse().with_context(|| {
+                    format!(
+                        "Invalid LAMBDA_PROXY_AUTH_TTL '{}' (expected seconds)",
+                        secs
+                    )
+                })?;
+                Some(Duration::from_secs(secs))
+            }
+            _ => None,
+        };
+
+        self.with_header_command(&name, &command, ttl)
+    }
+
+    /// Replace the retry budget shared by this client's requests
+    pub fn with_retry_budget(mut self, max_attempts: u32, max_delay: Duration) -> Self {
+        self.retry_budget = RetryBudget::new(max_attempts, max_delay);
+        self
+    }
+
+    /// Apply the retry budget from `LAMBDA_RETRY_BUDGET`, if set
+    fn with_env_retry_budget(mut self) -> Result<Self> {
+        if let Ok(spec) = std::env::var("LAMBDA_RETRY_BUDGET") {
+            self.retry_budget = RetryBudget::parse(&spec)?;
+        }
+        Ok(self)
+    }
+
+    /// Refuse to launch instances without a name, so every instance is attributable
+    pub fn with_require_name(mut self, require_name: bool) -> Self {
+        self.require_name = require_name;
+        self
+    }
+
+    /// Whether launches without a name are refused
+    pub fn requires_name(&self) -> bool {
+        self.require_name
+    }
+
+    /// Require names if `LAMBDA_REQUIRE_NAME` is set (to anything but empty, `0`, or `false`)
+    fn with_env_require_name(self) -> Self {
+        let required = std::env::var("LAMBDA_REQUIRE_NAME")
+            .is_ok_and(|v| !matches!(v.trim().to_lowercase().as_str(), "" | "0" | "false"));
+        self.with_require_name(required)
+    }
+
+    /// A view of this client with its own full retry budget, for one operation of a
+    /// long-lived caller (an MCP tool call, a `find` check).
+    ///
+    /// The view shares the HTTP connection pool and the cached API key and command
+    /// header, so only the retry budget is separate.
+    pub fn for_operation(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            api_key_source: self.api_key_source.clone(),
+            cached_api_key: Arc::clone(&self.cached_api_key),
+            api_key_ttl: self.api_key_ttl,
+            extra_headers: self.extra_headers.clone(),
+            retry_budget: self.retry_budget.fresh(),
+            command_header: self.command_header.clone(),
+            require_name: self.require_name,
+        }
+    }
+
+    /// Re-fetch a command-provided key after `ttl` (e.g. for short-lived tokens)
+    pub fn with_api_key_ttl(mut self, ttl: Duration) -> Self {
+        self.api_key_ttl = Some(ttl);
+        self
+    }
+
+    /// Apply the key TTL from `LAMBDA_API_KEY_TTL` (seconds), if set
+    fn with_env_api_key_ttl(self) -> Result<Self> {
+        match std::env::var("LAMBDA_API_KEY_TTL") {
+            Ok(secs) if !secs.is_empty() => {
+                let secs: u64 = secs.trim().parse().with_context(|| {
+                    format!("Invalid LAMBDA_API_KEY_TTL '{}' (expected seconds)", secs)
+                })?;
+                Ok(self.with_api_key_ttl(Duration::from_secs(secs)))
+            }
+            _ => Ok(self),
+        }
+    }
+
     /// Get the API key, executing the command if necessary (lazy evaluation)
     fn get_api_key(&self) -> Result<String> {
         match &self.api_key_source {
             ApiKeySource::Direct(key) => Ok(key.clone()),
+            ApiKeySource::Missing => Err(LambdaError::ApiKeyNotSet.into()),
             ApiKeySource::Command(cmd) => {
                 let mut cache = self
                     .cached_api_key
                     .lock()
                     .map_err(|e| anyhow!("Failed to acquire lock: {}", e))?;
 
-                if let Some(key) = cache.as_ref() {
-                    return Ok(key.clone());
+                if let Some((key, fetched)) = cache.as_ref() {
+                    if self.api_key_ttl.is_none_or(|ttl| fetched.elapsed() < ttl) {
+                        return Ok(key.clone());
+                    }
                 }
 
                 let key = execute_api_key_command(cmd)?;
-                *cache = Some(key.clone());
+                *cache = Some((key.clone(), Instant::now()));
                 Ok(key)
             }
         }
     }
 
+    /// Discard a cached command-provided key and run the command again.
+    ///
+    /// Requests already do this once on a 401; a key given directly is unchanged.
+    pub fn refresh_api_key(&self) -> Result<()> {
+        if let ApiKeySource::Command(_) = self.api_key_source {
+            self.cached_api_key
+                .lock()
+                .map_err(|e| anyhow!("Failed to acquire lock: {}", e))?
+                .take();
+            self.get_api_key()?;
+        }
+        Ok(())
+    }
+
     /// Validate the API key by making a test request
     pub async fn validate_api_key(&self) -> Result<()> {
-        let api_key = self.get_api_key()?;
-        let url = format!("{}/instances", API_BASE_URL);
-        let response = self
-            .client
-            .get(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", api_key))
-            .send()
-            .await
-            .context("Failed to connect to Lambda API")?;
+        self.send(
+            self.build_request(Method::GET, "/instances"),
+            "validate the API key",
+        )
+        .await?;
+        Ok(())
+    }
 
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            let status = response.status();
-            let error_msg = Self::parse_error_response(response).await;
-            Err(anyhow!(
-                "API key validation failed ({}): {}",
-                status,
-                error_msg
-            ))
-        }
+    /// Instance types keyed by name, from one request, for annotating many instances
+    pub async fn instance_types_by_name(&self) -> Result<HashMap<String, InstanceTypeData>> {
+        Ok(self
+            .list_instance_types()
+            .await?
+            .into_iter()
+            .map(|t| (t.name.clone(), t))
+            .collect())
     }
 
     /// List all available instance types
     pub async fn list_instance_types(&self) -> Result<Vec<InstanceTypeData>> {
-        let api_key = self.get_api_key()?;
-        let url = format!("{}/instance-types", API_BASE_URL);
-        let response = self
-            .client
-            .get(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", api_key))
-            .send()
-            .await
-            .context("Failed to fetch instance types")?;
-
-        if !response.status().is_success() {
-            let error_msg = Self::parse_error_response(response).await;
-            return Err(anyhow!("Failed to list instances: {}", error_msg));
-        }
-
-        let response: ApiResponse<HashMap<String, InstanceTypeResponse>> = response
-            .json()
-            .await
-            .context("Failed to parse instance types response")?;
+        let types: HashMap<String, InstanceTypeResponse> = self
+            .request(Method::GET, "/instance-types", None, "list instance types")
+            .await?;
 
-        let mut result: Vec<InstanceTypeData> = response
-            .data
+        let mut result: Vec<InstanceTypeData> = types
             .into_iter()
             .map(|(name, data)| InstanceTypeData {
                 name,
@@ -305,27 +759,11 @@ impl LambdaClient {
 
     /// Get instance type details (for checking availability)
     pub async fn get_instance_type(&self, gpu: &str) -> Result<Option<InstanceTypeResponse>> {
-        let api_key = self.get_api_key()?;
-        let url = format!("{}/instance-types", API_BASE_URL);
-        let response = self
-            .client
-            .get(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", api_key))
-            .send()
-            .await
-            .context("Failed to fetch instance types")?;
-
-        if !response.status().is_success() {
-            let error_msg = Self::parse_error_response(response).await;
-            return Err(anyhow!("Failed to get instance types: {}", error_msg));
-        }
-
-        let response: ApiResponse<HashMap<String, InstanceTypeResponse>> = response
-            .json()
-            .await
-            .context("Failed to parse instance types")?;
+        let mut types: HashMap<String, InstanceTypeResponse> = self
+            .request(Method::GET, "/instance-types", None, "get instance types")
+            .await?;
 
-        Ok(response.data.get(gpu).cloned())
+        Ok(types.remove(gpu))
     }
 
     /// Launch a new instance
@@ -349,6 +787,132 @@ impl LambdaClient {
         region: Option<&str>,
         filesystem: Option<&str>,
     ) -> Result<LaunchResult> {
+        let options = LaunchOptions {
+            name: name.map(String::from),
+            region: region.map(String::from),
+            filesystem: filesystem.map(String::from),
+            ..Default::default()
+        };
+        self.launch_instance_with_options(gpu, ssh_key, &options)
+            .await
+    }
+
+    /// Launch a new instance with the given options
+    pub async fn launch_instance_with_options(
+        &self,
+        gpu: &str,
+        ssh_key: &str,
+        options: &LaunchOptions,
+    ) -> Result<LaunchResult> {
+        if self.require_name && options.name.as_deref().is_none_or(str::is_empty) {
+            return Err(LambdaError::NameRequired.into());
+        }
+
+        // Catch filesystem typos and region mismatches before the API does
+        let filesystem_region = match options.filesystem.as_deref() {
+            Some(name) => Some(filesystem_region(&self.list_filesystems().await?, name)?),
+            None => None,
+        };
+
+        // Without an explicit region, launch next to the filesystem
+        let region = options.region.as_deref().map(normalize_region);
+        let region_name = match region.as_deref().or(filesystem_region.as_deref()) {
+            // Let the API accept or reject the region without a local capacity check
+            Some(r) if options.force_region => r.to_string(),
+            region => self.select_region(gpu, region).await?,
+        };
+
+        if let (Some(name), Some(fs_region)) = (&options.filesystem, filesystem_region) {
+            if fs_region != region_name {
+                return Err(LambdaError::FilesystemRegionMismatch {
+                    name: name.clone(),
+                    filesystem_region: fs_region,
+                    region: region_name,
+                }
+                .into());
+            }
+        }
+
+        let mut payload = serde_json::json!({
+            "region_name": region_name,
+            "instance_type_name": gpu,
+            "ssh_key_names": [ssh_key],
+            "quantity": 1
+        });
+
+        if let Some(instance_name) = options.name.as_deref() {
+            payload["name"] = serde_json::Value::String(instance_name.to_string());
+        }
+
+        if let Some(fs_name) = options.filesystem.as_deref() {
+            payload["file_system_names"] = serde_json::json!([fs_name]);
+        }
+
+        let mut request = self
+            .build_request(Method::POST, "/instance-operations/launch")
+            .json(&payload);
+        if let Some(timeout) = options.timeout {
+            request = request.timeout(timeout);
+        }
+
+        let launched: LaunchResponse = match self.fetch(request, "launch an instance").await {
+            Ok(launched) => launched,
+            Err(e) if options.force_region && is_launch_rejection(&e) => {
+                // The server rejected a forced region; show where capacity actually is
+                let available = match self.check_availability(gpu).await {
+                    Ok(regions) if !regions.is_empty() => regions.join(", "),
+                    Ok(_) => "none".to_string(),
+                    Err(_) => "unknown".to_string(),
+                };
+                return Err(forced_region_error(e, &region_name, gpu, &available));
+            }
+            Err(e) => return Err(e),
+        };
+
+        let instance_id = launched
+            .instance_ids
+            .first()
+            .ok_or(LambdaError::NoInstanceIds)?
+            .clone();
+
+        Ok(LaunchResult {
+            instance_id,
+            region: region_name,
+            ip: None,
+            ssh_command: None,
+        })
+    }
+
+    /// Launch an instance and wait until it is active, returning it with its IP.
+    ///
+    /// If the launch succeeds but the instance fails or doesn't become active
+    /// within `max_wait`, the error is [`LambdaError::LaunchedNotReady`], which
+    /// carries the new instance's ID so the caller can check on or terminate it.
+    pub async fn launch_and_wait(
+        &self,
+        gpu: &str,
+        ssh_key: &str,
+        options: &LaunchOptions,
+        poll_interval: Duration,
+        max_wait: Duration,
+    ) -> Result<Instance> {
+        let launched = self
+            .launch_instance_with_options(gpu, ssh_key, options)
+            .await?;
+        self.poll_until_active(&launched.instance_id, poll_interval, max_wait)
+            .await
+            .map_err(|e| {
+                LambdaError::LaunchedNotReady {
+                    instance_id: launched.instance_id,
+                    region: launched.region,
+                    reason: e.to_string(),
+                }
+                .into()
+            })
+    }
+
+    /// Pick the launch region, validating a requested one against current capacity
+    async fn select_region(&self, gpu: &str, region: Option<&str>) -> Result<String> {
         let instance_type_response = self
             .get_instance_type(gpu)
             .await?
@@ -361,17 +925,17 @@ impl LambdaClient {
                 .iter()
                 .any(|reg| reg.name == r)
             {
-                return Err(anyhow!(
-                    "Region '{}' is not available for instance type '{}'. Available regions: {}",
-                    r,
-                    gpu,
-                    instance_type_response
+                return Err(LambdaError::RegionNotAvailable {
+                    region: r.to_string(),
+                    gpu: gpu.to_string(),
+                    available: instance_type_response
                         .regions_with_capacity_available
                         .iter()
                         .map(|reg| reg.name.as_str())
                         .collect::<Vec<_>>()
-                        .join(", ")
-                ));
+                        .join(", "),
+                }
+                .into());
             }
             r.to_string()
         } else {
@@ -384,134 +948,218 @@ impl LambdaClient {
                 .clone()
         };
 
-        let url = format!("{}/instance-operations/launch", API_BASE_URL);
-
-        let mut payload = serde_json::json!({
-            "region_name": region_name,
-            "instance_type_name": gpu,
-            "ssh_key_names": [ssh_key],
-            "quantity": 1
-        });
-
-        if let Some(instance_name) = name {
-            payload["name"] = serde_json::Value::String(instance_name.to_string());
-        }
-
-        if let Some(fs_name) = filesystem {
-            payload["file_system_names"] = serde_json::json!([fs_name]);
-        }
-
-        let api_key = self.get_api_key()?;
-        let response = self
-            .client
-            .post(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", api_key))
-            .json(&payload)
-            .send()
-            .await
-            .context("Failed to send launch request")?;
-
-        if !response.status().is_success() {
-            let error_msg = Self::parse_error_response(response).await;
-            return Err(anyhow!("Failed to launch instance: {}", error_msg));
-        }
+        Ok(region_name)
+    }
 
-        let parsed_response: ApiResponse<LaunchResponse> = response
-            .json()
+    /// Replace an instance with one of the same type, region, SSH key, and name,
+    /// attached to `filesystem` instead (or to no filesystem).
+    ///
+    /// The API only attaches filesystems at launch, so the replacement is launched
+    /// first and the original is terminated only once that succeeds. Anything on
+    /// the original's local disk is lost, and the replacement has a new ID and IP.
+    /// Returns the spec the replacement was launched from along with it.
+    pub async fn relaunch_with_filesystem(
+        &self,
+        instance_id: &str,
+        filesystem: Option<&str>,
+    ) -> Result<(LaunchResult, crate::spec::InstanceSpec)> {
+        let instance = self.get_instance(instance_id).await?;
+        let mut spec = crate::spec::InstanceSpec::from_instance(&instance)
+            .map_err(|reason| anyhow!("Can't relaunch instance {}: {}", instance_id, reason))?;
+        spec.file_system_names = filesystem.map(String::from).into_iter().collect();
+
+        let ssh_key = spec.ssh_key().unwrap_or_default();
+        let result = self
+            .launch_instance_with_options(&spec.instance_type, ssh_key, &spec.launch_options())
+            .await?;
+
+        self.terminate_instance(instance_id)
             .await
-            .context("Failed to parse launch response")?;
-
-        let instance_id = parsed_response
-            .data
-            .instance_ids
-            .first()
-            .ok_or(LambdaError::NoInstanceIds)?
-            .clone();
-
-        Ok(LaunchResult {
-            instance_id,
-            region: region_name,
-        })
+            .with_context(|| {
+                format!(
+                    "Launched replacement {}, but failed to terminate the original {}",
+                    result.instance_id, instance_id
+                )
+            })?;
+        Ok((result, spec))
     }
 
     /// Terminate an instance
     pub async fn terminate_instance(&self, instance_id: &str) -> Result<()> {
-        let api_key = self.get_api_key()?;
-        let url = format!("{}/instance-operations/terminate", API_BASE_URL);
+        self.terminate_instances(&[instance_id.to_string()])
+            .await
+            .map(drop)
+    }
+
+    /// Terminate several instances in one request, returning the IDs the API reports terminated
+    pub async fn terminate_instances(&self, instance_ids: &[String]) -> Result<Vec<String>> {
         let payload = serde_json::json!({
-            "instance_ids": [instance_id]
+            "instance_ids": instance_ids
         });
 
-        let response = self
-            .client
-            .post(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", api_key))
-            .json(&payload)
-            .send()
-            .await
-            .context("Failed to send terminate request")?;
-
-        if !response.status().is_success() {
-            let error_msg = Self::parse_error_response(response).await;
-            return Err(anyhow!("Failed to terminate instance: {}", error_msg));
-        }
+        let action = match instance_ids.len() {
+            1 => "terminate an instance",
+            _ => "terminate instances",
+        };
 
-        Ok(())
+        let response: TerminateResponse = self
+            .request(
+                Method::POST,
+                "/instance-operations/terminate",
+                Some(&payload),
+                action,
+            )
+            .await?;
+        Ok(response
+            .terminated_instances
+            .into_iter()
+            .filter_map(|instance| instance.id)
+            .collect())
     }
 
     /// List all running instances
     pub async fn list_running_instances(&self) -> Result<Vec<Instance>> {
-        let api_key = self.get_api_key()?;
-        let url = format!("{}/instances", API_BASE_URL);
-        let response = self
-            .client
-            .get(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", api_key))
-            .send()
-            .await
-            .context("Failed to fetch running instances")?;
-
-        if !response.status().is_success() {
-            let error_msg = Self::parse_error_response(response).await;
-            return Err(anyhow!("Failed to list running instances: {}", error_msg));
-        }
-
-        let response: ApiResponse<Vec<Instance>> = response
-            .json()
+        self.request(Method::GET, "/instances", None, "list running instances")
             .await
-            .context("Failed to parse running instances response")?;
+    }
 
-        Ok(response.data)
+    /// Poll one instance until it is active, has failed, or `max_wait` passes
+    pub async fn poll_until_active(
+        &self,
+        instance_id: &str,
+        poll_interval: Duration,
+        max_wait: Duration,
+    ) -> Result<Instance> {
+        self.poll_many_until_active(
+            &[instance_id.to_string()],
+            poll_interval,
+            max_wait,
+            |_, _| {},
+        )
+        .await
+        .pop()
+        .map(|(_, result)| result)
+        .unwrap_or_else(|| Err(anyhow!("No result polling instance {}", instance_id)))
     }
 
-    /// Get details for a specific instance
-    pub async fn get_instance(&self, instance_id: &str) -> Result<Instance> {
-        let api_key = self.get_api_key()?;
-        let url = format!("{}/instances/{}", API_BASE_URL, instance_id);
-        let response = self
-            .client
-            .get(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", api_key))
-            .send()
-            .await
-            .context("Failed to fetch instance details")?;
+    /// Poll several instances until each is active, has failed, or `max_wait` passes.
+    ///
+    /// Every round checks all pending instances with one list request, and
+    /// `on_resolved` is called as each instance settles, so one slow instance
+    /// doesn't hold up reporting the others. IDs missing from the first listing
+    /// are looked up one by one, so a mistyped or terminated ID fails right
+    /// away as it would for a single instance. Results follow the order of `ids`.
+    pub async fn poll_many_until_active(
+        &self,
+        ids: &[String],
+        poll_interval: Duration,
+        max_wait: Duration,
+        mut on_resolved: impl FnMut(&str, &Result<Instance>),
+    ) -> Vec<(String, Result<Instance>)> {
+        let start = Instant::now();
+        let mut results: Vec<Option<Result<Instance>>> = ids.iter().map(|_| None).collect();
+        let mut last_error: Option<String> = None;
+        let mut looked_up_unlisted = false;
+
+        while results.iter().any(Option::is_none) {
+            if start.elapsed() > max_wait {
+                for (id, result) in ids.iter().zip(results.iter_mut()) {
+                    if result.is_none() {
+                        let detail = last_error
+                            .as_deref()
+                            .map(|e| format!(" (last error: {})", e))
+                            .unwrap_or_default();
+                        let timed_out = Err(anyhow!(
+                            "Timed out after {}s waiting for instance {}{}",
+                            max_wait.as_secs(),
+                            id,
+                            detail
+                        ));
+                        on_resolved(id, &timed_out);
+                        *result = Some(timed_out);
+                    }
+                }
+                break;
+            }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_msg = Self::parse_error_response(response).await;
-            return Err(anyhow!(
-                "Failed to get instance details ({}): {}",
-                status,
-                error_msg
-            ));
-        }
+            tokio::time::sleep(poll_interval).await;
 
-        let response: ApiResponse<Instance> = response
-            .json()
-            .await
-            .context("Failed to parse instance details")?;
+            let mut instances = match self.list_running_instances().await {
+                Ok(instances) => instances,
+                Err(e) => {
+                    last_error = Some(e.to_string());
+                    continue;
+                }
+            };
+
+            if !looked_up_unlisted {
+                looked_up_unlisted = true;
+                for i in unlisted(ids, &instances) {
+                    match self.get_instance(&ids[i]).await {
+                        Ok(instance) => instances.push(instance),
+                        Err(e) => {
+                            let failed = Err(e);
+                            on_resolved(&ids[i], &failed);
+                            results[i] = Some(failed);
+                        }
+                    }
+                }
+            }
+
+            for (id, result) in ids.iter().zip(results.iter_mut()) {
+                if result.is_some() {
+                    continue;
+                }
+                let Some(resolved) = instances
+                    .iter()
+                    .find(|i| i.id.as_ref() == Some(id))
+                    .and_then(|instance| settled(id, instance))
+                else {
+                    continue;
+                };
+                on_resolved(id, &resolved);
+                *result = Some(resolved);
+            }
+        }
+
+        ids.iter()
+            .cloned()
+            .zip(results.into_iter().flatten())
+            .collect()
+    }
+
+    /// List running instances of the given instance type
+    pub async fn list_running_by_type(&self, gpu: &str) -> Result<Vec<Instance>> {
+        let mut instances = self.list_running_instances().await?;
+        instances.retain(|inst| inst.type_name() == Some(gpu));
+        Ok(instances)
+    }
+
+    /// Count running instances of the given instance type
+    pub async fn count_running_by_type(&self, gpu: &str) -> Result<usize> {
+        Ok(self.list_running_by_type(gpu).await?.len())
+    }
+
+    /// Get details for a specific instance
+    pub async fn get_instance(&self, instance_id: &str) -> Result<Instance> {
+        self.request(
+            Method::GET,
+            &format!("/instances/{}", instance_id),
+            None,
+            "get instance details",
+        )
+        .await
+    }
 
-        Ok(response.data)
+    /// Get the unmodified response body for an instance, even if it doesn't fit `Instance`
+    pub async fn get_instance_raw(&self, instance_id: &str) -> Result<String> {
+        let action = "get instance details";
+        let request = self.build_request(Method::GET, &format!("/instances/{}", instance_id));
+        self.send(request, action)
+            .await?
+            .text()
+            .await
+            .with_context(|| format!("Failed to read response ({})", action))
     }
 
     /// Check if a GPU type is available
@@ -530,98 +1178,464 @@ impl LambdaClient {
 
     /// List all filesystems
     pub async fn list_filesystems(&self) -> Result<Vec<Filesystem>> {
-        let api_key = self.get_api_key()?;
-        let url = format!("{}/file-systems", API_BASE_URL);
-        let response = self
-            .client
-            .get(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", api_key))
-            .send()
+        self.request(Method::GET, "/file-systems", None, "list filesystems")
             .await
-            .context("Failed to fetch filesystems")?;
-
-        if !response.status().is_success() {
-            let error_msg = Self::parse_error_response(response).await;
-            return Err(anyhow!("Failed to list filesystems: {}", error_msg));
-        }
-
-        let response: ApiResponse<Vec<Filesystem>> = response
-            .json()
-            .await
-            .context("Failed to parse filesystems response")?;
+    }
 
-        Ok(response.data)
+    /// Get current details for a filesystem by ID.
+    ///
+    /// The API has no per-filesystem endpoint, so this reads a fresh listing.
+    pub async fn get_filesystem(&self, filesystem_id: &str) -> Result<Filesystem> {
+        self.list_filesystems()
+            .await?
+            .into_iter()
+            .find(|fs| fs.id == filesystem_id)
+            .ok_or_else(|| anyhow!("Filesystem '{}' not found", filesystem_id))
     }
 
     /// Create a new filesystem
     pub async fn create_filesystem(&self, name: &str, region: &str) -> Result<Filesystem> {
-        let api_key = self.get_api_key()?;
-        let url = format!("{}/file-systems", API_BASE_URL);
         let payload = serde_json::json!({
             "name": name,
-            "region_name": region
+            "region_name": normalize_region(region)
         });
 
-        let response = self
-            .client
-            .post(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", api_key))
-            .json(&payload)
-            .send()
-            .await
-            .context("Failed to create filesystem")?;
+        self.request(
+            Method::POST,
+            "/file-systems",
+            Some(&payload),
+            "create a filesystem",
+        )
+        .await
+    }
+
+    /// Delete a filesystem
+    pub async fn delete_filesystem(&self, filesystem_id: &str) -> Result<()> {
+        self.send(
+            self.build_request(Method::DELETE, &format!("/file-systems/{}", filesystem_id)),
+            "delete a filesystem",
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Start a request to `path`, relative to the API base URL
+    fn build_request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", API_BASE_URL, path))
+            .headers(self.extra_headers.clone())
+    }
+
+    /// Authenticate and send a request, turning non-success responses into errors.
+    ///
+    /// Rate limits, and transient failures of idempotent requests, are retried
+    /// while the client's retry budget lasts. A 401 with a command-provided key
+    /// refreshes the key and retries once, in case it rotated.
+    async fn send(&self, request: RequestBuilder, action: &str) -> Result<Response> {
+        let request = request
+            .build()
+            .with_context(|| format!("Failed to {}", action))?;
+        let idempotent = matches!(*request.method(), Method::GET | Method::DELETE);
+        let mut refreshed_key = false;
+
+        loop {
+            let Some(mut attempt) = request.try_clone() else {
+                let request = self.authorize(request)?;
+                return Self::check_response(self.client.execute(request).await, action).await;
+            };
+            attempt = self.authorize(attempt)?;
+
+            let result = self.client.execute(attempt).await;
+            let (retryable, requested) = match &result {
+                Ok(response)
+                    if response.status() == StatusCode::UNAUTHORIZED
+                        && !refreshed_key
+                        && matches!(self.api_key_source, ApiKeySource::Command(_)) =>
+                {
+                    refreshed_key = true;
+                    self.refresh_api_key()?;
+                    continue;
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let retryable = status == StatusCode::TOO_MANY_REQUESTS
+                        || (idempotent && status.is_server_error());
+                    (retryable, retry_after(response))
+                }
+                // A connection that never opened can't have reached the API
+                Err(e) => (e.is_connect() || (idempotent && e.is_timeout()), None),
+            };
+
+            let delay = if retryable {
+                self.retry_budget.acquire(requested)
+            } else {
+                None
+            };
+            match delay {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Self::check_response(result, action).await,
+            }
+        }
+    }
+
+    /// Add the bearer token to a request
+    fn authorize(&self, mut request: Request) -> Result<Request> {
+        let value = HeaderValue::from_str(&format!("Bearer {}", self.get_api_key()?))
+            .context("API key contains characters not allowed in a header")?;
+        request.headers_mut().insert(AUTHORIZATION, value);
+        if let Some(header) = &self.command_header {
+            request
+                .headers_mut()
+                .insert(header.name.clone(), header.value()?);
+        }
+        Ok(request)
+    }
+
+    /// Turn a send failure or non-success response into an error
+    async fn check_response(result: reqwest::Result<Response>, action: &str) -> Result<Response> {
+        let response = result.with_context(|| format!("Failed to {}", action))?;
 
         if !response.status().is_success() {
-            let error_msg = Self::parse_error_response(response).await;
-            return Err(anyhow!("Failed to create filesystem: {}", error_msg));
+            return Err(Self::error_for_response(response, action).await);
         }
 
-        let response: ApiResponse<Filesystem> = response
-            .json()
-            .await
-            .context("Failed to parse create filesystem response")?;
+        Ok(response)
+    }
+
+    /// Send a request and deserialize the `data` field of its response
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+        action: &str,
+    ) -> Result<T> {
+        let mut request = self.build_request(method, path);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
 
-        Ok(response.data)
+        self.fetch(request, action).await
     }
 
-    /// Delete a filesystem
-    pub async fn delete_filesystem(&self, filesystem_id: &str) -> Result<()> {
-        let api_key = self.get_api_key()?;
-        let url = format!("{}/file-systems/{}", API_BASE_URL, filesystem_id);
-
-        let response = self
-            .client
-            .delete(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", api_key))
-            .send()
+    /// Send a prepared request and deserialize the `data` field of its response
+    async fn fetch<T: DeserializeOwned>(&self, request: RequestBuilder, action: &str) -> Result<T> {
+        let body = self
+            .send(request, action)
+            .await?
+            .text()
             .await
-            .context("Failed to delete filesystem")?;
+            .with_context(|| format!("Failed to read response ({})", action))?;
 
-        if !response.status().is_success() {
-            let error_msg = Self::parse_error_response(response).await;
-            return Err(anyhow!("Failed to delete filesystem: {}", error_msg));
-        }
+        parse_api_response(&body, action)
+    }
 
-        Ok(())
+    /// Turn a non-success response into an error for the given action
+    async fn error_for_response(response: Response, action: &str) -> anyhow::Error {
+        let status = response.status();
+        let (code, message) = Self::parse_error_response(response).await;
+        api_error(status, action, code.as_deref(), &message)
     }
 
-    async fn parse_error_response(response: reqwest::Response) -> String {
+    async fn parse_error_response(response: Response) -> (Option<String>, String) {
         response
             .json::<ApiErrorResponse>()
             .await
-            .map(|e| e.error.message)
-            .unwrap_or_else(|_| "Unknown error".to_string())
+            .map(|e| (e.error.code, e.error.message))
+            .unwrap_or_else(|_| (None, "Unknown error".to_string()))
     }
 }
 
+/// Map an HTTP error status to an error, with dedicated variants for auth and account failures.
+///
+/// Account problems are matched on the API's error `code` when it sends one,
+/// otherwise on the message, and take precedence over the status code.
+fn api_error(status: StatusCode, action: &str, code: Option<&str>, message: &str) -> anyhow::Error {
+    let lower = message.to_lowercase();
+    if code == Some("global/account-inactive")
+        || (lower.contains("account")
+            && ["inactive", "suspended", "deactivated", "disabled"]
+                .iter()
+                .any(|word| lower.contains(word)))
+    {
+        return LambdaError::AccountSuspended {
+            status,
+            message: message.to_string(),
+        }
+        .into();
+    }
+    if code == Some("global/quota-exceeded") || lower.contains("quota") {
+        return LambdaError::QuotaExceeded {
+            action: action.to_string(),
+            status,
+            message: message.to_string(),
+        }
+        .into();
+    }
+
+    match status {
+        StatusCode::UNAUTHORIZED => LambdaError::Unauthorized.into(),
+        StatusCode::FORBIDDEN => LambdaError::Forbidden(action.to_string()).into(),
+        _ => LambdaError::ApiStatus {
+            action: action.to_string(),
+            status,
+            code: code.map(String::from),
+            message: message.to_string(),
+        }
+        .into(),
+    }
+}
+
+/// Indices of the `ids` that `instances` doesn't include
+fn unlisted(ids: &[String], instances: &[Instance]) -> Vec<usize> {
+    (0..ids.len())
+        .filter(|&i| {
+            !instances
+                .iter()
+                .any(|inst| inst.id.as_ref() == Some(&ids[i]))
+        })
+        .collect()
+}
+
+/// The outcome `instance` settles on, or `None` while it's still starting
+fn settled(id: &str, instance: &Instance) -> Option<Result<Instance>> {
+    match instance.status.as_ref() {
+        Some(InstanceStatus::Active) => Some(Ok(instance.clone())),
+        Some(s) if s.is_failed() => Some(Err(anyhow!(
+            "Instance {} entered {} state",
+            id,
+            instance.status_display()
+        ))),
+        _ => None,
+    }
+}
+
+/// Whether the API turned a launch request down (a 4xx), as opposed to a
+/// timeout, connection failure, or server error, after which the instance
+/// may still have been created
+fn is_launch_rejection(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<LambdaError>(),
+        Some(LambdaError::ApiStatus { status, .. }) if status.is_client_error()
+    )
+}
+
+/// Add where capacity actually is to a launch rejected in a forced region.
+///
+/// The source error stays in the chain, so its `LambdaError` kind, status, and
+/// `is_no_capacity()` still apply.
+fn forced_region_error(
+    e: anyhow::Error,
+    region: &str,
+    gpu: &str,
+    available: &str,
+) -> anyhow::Error {
+    let message = format!(
+        "{}. Region '{}' may not have capacity for instance type '{}'. Available regions: {}",
+        e.to_string().trim_end_matches('.'),
+        region,
+        gpu,
+        available
+    );
+    e.context(message)
+}
+
+/// Parse the `data` field of a successful response body.
+///
+/// On a shape mismatch the error quotes a redacted snippet of the body, so
+/// unexpected API changes can be diagnosed from the message alone.
+fn parse_api_response<T: DeserializeOwned>(body: &str, action: &str) -> Result<T> {
+    serde_json::from_str::<ApiResponse<T>>(body)
+        .map(|response| response.data)
+        .map_err(|e| {
+            anyhow!(
+                "Failed to parse response ({}): {}. Response body: {}",
+                action,
+                e,
+                body_snippet(body)
+            )
+        })
+}
+
+/// Shorten a response body for an error message, redacting secret-looking fields
+fn body_snippet(body: &str) -> String {
+    let text = match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            redact_secrets(&mut value);
+            value.to_string()
+        }
+        Err(_) => body.split_whitespace().collect::<Vec<_>>().join(" "),
+    };
+
+    if text.is_empty() {
+        return "<empty>".to_string();
+    }
+    match text.char_indices().nth(BODY_SNIPPET_LEN) {
+        Some((idx, _)) => format!("{}...", &text[..idx]),
+        None => text,
+    }
+}
+
+fn redact_secrets(value: &mut serde_json::Value) {
+    const SECRET_MARKERS: [&str; 4] = ["key", "token", "secret", "password"];
+
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                let k = k.to_lowercase();
+                if v.is_string() && SECRET_MARKERS.iter().any(|m| k.contains(m)) {
+                    *v = serde_json::Value::String("[REDACTED]".to_string());
+                } else {
+                    redact_secrets(v);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// Optional settings for a launch request
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    pub name: Option<String>,
+    /// Region to launch in (auto-selects the first region with capacity if not set)
+    pub region: Option<String>,
+    /// Filesystem to attach (must be in the same region)
+    pub filesystem: Option<String>,
+    /// Skip the local capacity check for `region` and let the API decide
+    pub force_region: bool,
+    /// Timeout for the launch request itself, instead of the client default
+    pub timeout: Option<Duration>,
+}
+
 #[derive(Debug, Clone)]
 pub struct LaunchResult {
     pub instance_id: String,
     pub region: String,
+    /// Public IP, once a readiness poll has seen one (`None` straight after launch)
+    pub ip: Option<String>,
+    /// Command to connect, set together with `ip`
+    pub ssh_command: Option<String>,
+}
+
+impl LaunchResult {
+    /// Record the IP found by a readiness poll, filling in `ssh_command`
+    pub fn set_ip(&mut self, ip: &str) {
+        self.ip = Some(ip.to_string());
+        self.ssh_command = Some(ssh_command(ip));
+    }
+}
+
+/// Command to SSH into an instance at `ip`
+pub fn ssh_command(ip: &str) -> String {
+    format!("ssh ubuntu@{}", ip)
+}
+
+/// Canonical form of a region name typed by a user: `US_East_1` → `us-east-1`
+pub fn normalize_region(region: &str) -> String {
+    region.trim().to_lowercase().replace('_', "-")
+}
+
+/// Short human-readable duration, e.g. `45s`, `2m14s`, `1h05m`
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, _) => format!("{}h{:02}m", h, m),
+    }
+}
+
+/// Region of the filesystem called `name`
+fn filesystem_region(filesystems: &[Filesystem], name: &str) -> Result<String, LambdaError> {
+    if let Some(fs) = filesystems.iter().find(|fs| fs.name == name) {
+        return Ok(fs.region.name.clone());
+    }
+
+    let available = if filesystems.is_empty() {
+        "none".to_string()
+    } else {
+        filesystems
+            .iter()
+            .map(|fs| fs.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    Err(LambdaError::FilesystemNotFound(name.to_string(), available))
+}
+
+/// Delay requested by a `Retry-After` header given in seconds
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Parse a comma-separated `name=value` header list (as in `LAMBDA_EXTRA_HEADERS`)
+fn parse_header_list(list: &str) -> Result<Vec<(&str, &str)>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid header '{}' (expected name=value)", entry))
+        })
+        .collect()
+}
+
+/// Name of the variable a `$NAME` setting value refers to, if it is one
+fn env_reference(value: &str) -> Option<&str> {
+    let name = value.trim().strip_prefix('$')?;
+    let mut chars = name.chars();
+    let starts_ok = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    (starts_ok && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')).then_some(name)
+}
+
+/// Resolve a `$NAME` setting value to the contents of that variable.
+///
+/// Lets configs that don't expand variables themselves (e.g. an MCP client's
+/// `env` block) point at a secret held elsewhere. Other values pass through.
+fn resolve_env_reference(setting: &str, value: String) -> Result<String> {
+    let Some(name) = env_reference(&value) else {
+        return Ok(value);
+    };
+    match std::env::var(name) {
+        Ok(resolved) if !resolved.is_empty() => Ok(resolved),
+        _ => Err(anyhow!("{} refers to ${}, which is not set", setting, name)),
+    }
+}
+
+/// Read an API key from the first line of `reader`
+fn read_api_key(mut reader: impl std::io::BufRead) -> Result<String> {
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .context("Failed to read API key from stdin")?;
+
+    let key = line.trim();
+    if key.is_empty() {
+        return Err(anyhow!("No API key provided on stdin"));
+    }
+    Ok(key.to_string())
 }
 
 /// Execute a shell command to retrieve the API key.
 fn execute_api_key_command(command: &str) -> Result<String> {
+    execute_command(command).map_err(|e| LambdaError::ApiKeyCommandFailed(e).into())
+}
+
+/// Run a shell command and return its trimmed, non-empty output
+fn execute_command(command: &str) -> Result<String, String> {
     use std::process::Command;
 
     let output = if cfg!(target_os = "windows") {
@@ -633,28 +1647,18 @@ fn execute_api_key_command(command: &str) -> Result<String> {
     match output {
         Ok(output) => {
             if output.status.success() {
-                let key = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                if key.is_empty() {
-                    Err(LambdaError::ApiKeyCommandFailed(
-                        "Command returned empty output".to_string(),
-                    )
-                    .into())
+                let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if value.is_empty() {
+                    Err("Command returned empty output".to_string())
                 } else {
-                    Ok(key)
+                    Ok(value)
                 }
             } else {
                 let stderr = String::from_utf8_lossy(&output.stderr);
-                Err(
-                    LambdaError::ApiKeyCommandFailed(format!("Command failed: {}", stderr.trim()))
-                        .into(),
-                )
+                Err(format!("Command failed: {}", stderr.trim()))
             }
         }
-        Err(e) => Err(LambdaError::ApiKeyCommandFailed(format!(
-            "Failed to execute command: {}",
-            e
-        ))
-        .into()),
+        Err(e) => Err(format!("Failed to execute command: {}", e)),
     }
 }
 
@@ -674,6 +1678,462 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_api_error_auth_variants() {
+        let err = api_error(
+            StatusCode::UNAUTHORIZED,
+            "list filesystems",
+            None,
+            "Invalid key",
+        );
+        assert!(matches!(
+            err.downcast_ref::<LambdaError>(),
+            Some(LambdaError::Unauthorized)
+        ));
+
+        let err = api_error(
+            StatusCode::FORBIDDEN,
+            "launch an instance",
+            None,
+            "Forbidden",
+        );
+        assert_eq!(
+            err.to_string(),
+            "API key lacks permission to launch an instance (403 Forbidden). Use a key with access to this operation"
+        );
+
+        let err = api_error(
+            StatusCode::BAD_REQUEST,
+            "launch an instance",
+            None,
+            "Bad region",
+        );
+        assert_eq!(
+            err.to_string(),
+            "Failed to launch an instance (400 Bad Request): Bad region"
+        );
+        let err = err.downcast_ref::<LambdaError>().unwrap();
+        assert_eq!(err.kind(), "api_error");
+        assert_eq!(err.status_code(), Some(400));
+        assert_eq!(LambdaError::Unauthorized.status_code(), Some(401));
+        assert_eq!(LambdaError::ApiKeyNotSet.status_code(), None);
+        assert!(!err.is_no_capacity());
+
+        let err = api_error(
+            StatusCode::BAD_REQUEST,
+            "launch an instance",
+            None,
+            "Not enough capacity to fulfill launch request.",
+        );
+        assert!(err.downcast_ref::<LambdaError>().unwrap().is_no_capacity());
+
+        // A code decides over the message
+        let is_no_capacity = |code, message| {
+            api_error(StatusCode::BAD_REQUEST, "launch an instance", code, message)
+                .downcast_ref::<LambdaError>()
+                .unwrap()
+                .is_no_capacity()
+        };
+        assert!(is_no_capacity(
+            Some("instance-operations/launch/insufficient-capacity"),
+            "Not enough capacity to fulfill launch request."
+        ));
+        assert!(!is_no_capacity(
+            Some("global/invalid-parameters"),
+            "Invalid capacity reservation ID"
+        ));
+    }
+
+    #[test]
+    fn test_poll_many_listing() {
+        let listing: Vec<Instance> = serde_json::from_value(serde_json::json!([
+            {"id": "i-active", "status": "active"},
+            {"id": "i-booting", "status": "booting"},
+            {"id": "i-failed", "status": "unhealthy"},
+        ]))
+        .unwrap();
+        let ids: Vec<String> = ["i-active", "i-typo", "i-booting", "i-failed", "i-gone"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+
+        // These get looked up directly instead of waiting out max_wait
+        assert_eq!(unlisted(&ids, &listing), vec![1, 4]);
+
+        assert!(matches!(settled("i-active", &listing[0]), Some(Ok(_))));
+        assert!(settled("i-booting", &listing[1]).is_none());
+        let failed = settled("i-failed", &listing[2]).unwrap().unwrap_err();
+        assert_eq!(
+            failed.to_string(),
+            "Instance i-failed entered unhealthy state"
+        );
+    }
+
+    #[test]
+    fn test_forced_region_error_keeps_source() {
+        let rejected = api_error(
+            StatusCode::BAD_REQUEST,
+            "launch an instance",
+            None,
+            "Not enough capacity to fulfill launch request.",
+        );
+        let err = forced_region_error(rejected, "us-east-1", "gpu_1x_a10", "us-west-2");
+        assert_eq!(
+            err.to_string(),
+            "Failed to launch an instance (400 Bad Request): Not enough capacity to fulfill launch request. \
+             Region 'us-east-1' may not have capacity for instance type 'gpu_1x_a10'. Available regions: us-west-2"
+        );
+        let source = err.downcast_ref::<LambdaError>().unwrap();
+        assert!(matches!(source, LambdaError::ApiStatus { .. }));
+        assert!(source.is_no_capacity());
+        assert_eq!(source.status_code(), Some(400));
+
+        // Only rejections get the hint; the launch may have gone through otherwise
+        let rejected = api_error(StatusCode::BAD_REQUEST, "launch an instance", None, "No");
+        assert!(is_launch_rejection(&rejected));
+        let server_error = api_error(
+            StatusCode::BAD_GATEWAY,
+            "launch an instance",
+            None,
+            "Bad gateway",
+        );
+        assert!(!is_launch_rejection(&server_error));
+        assert!(!is_launch_rejection(&anyhow!("operation timed out")));
+        assert!(!is_launch_rejection(&LambdaError::Unauthorized.into()));
+    }
+
+    #[test]
+    fn test_api_error_account_variants() {
+        let detail = |body: &str| {
+            serde_json::from_str::<ApiErrorResponse>(body)
+                .unwrap()
+                .error
+        };
+        let kind = |status, detail: ApiErrorDetail| {
+            let err = api_error(
+                status,
+                "launch an instance",
+                detail.code.as_deref(),
+                &detail.message,
+            );
+            let err = err.downcast::<LambdaError>().unwrap();
+            (err.kind(), err.status_code())
+        };
+
+        let inactive = detail(
+            r#"{"error": {"code": "global/account-inactive", "message": "Your account is inactive.", "suggestion": "Make sure you have verified your email address and have a valid payment method."}}"#,
+        );
+        assert_eq!(
+            kind(StatusCode::FORBIDDEN, inactive),
+            ("account_suspended", Some(403))
+        );
+        let suspended = detail(r#"{"error": {"message": "This account has been suspended"}}"#);
+        assert_eq!(
+            kind(StatusCode::BAD_REQUEST, suspended),
+            ("account_suspended", Some(400))
+        );
+
+        let quota = detail(
+            r#"{"error": {"code": "global/quota-exceeded", "message": "Quota exceeded", "suggestion": "Contact Support to increase your quota."}}"#,
+        );
+        assert_eq!(
+            kind(StatusCode::BAD_REQUEST, quota),
+            ("quota_exceeded", Some(400))
+        );
+        let quota = detail(r#"{"error": {"message": "Instance quota reached for gpu_8x_h100"}}"#);
+        assert_eq!(
+            kind(StatusCode::FORBIDDEN, quota),
+            ("quota_exceeded", Some(403))
+        );
+
+        // Other 403s and account mentions stay as they were
+        let forbidden =
+            detail(r#"{"error": {"code": "global/forbidden", "message": "Forbidden"}}"#);
+        assert_eq!(
+            kind(StatusCode::FORBIDDEN, forbidden),
+            ("forbidden", Some(403))
+        );
+        let unrelated = detail(r#"{"error": {"message": "SSH key not found in account"}}"#);
+        assert_eq!(
+            kind(StatusCode::BAD_REQUEST, unrelated),
+            ("api_error", Some(400))
+        );
+
+        let err = api_error(
+            StatusCode::BAD_REQUEST,
+            "launch an instance",
+            None,
+            "Quota exceeded",
+        );
+        assert!(err.to_string().starts_with(
+            "Quota exceeded trying to launch an instance (400 Bad Request): Quota exceeded. Retrying won't help"
+        ));
+    }
+
+    #[test]
+    fn test_parse_malformed_launch_response() {
+        let body = r#"{"data": {"instance_id": "i-123", "token": "hunter2"}}"#;
+        let err = parse_api_response::<LaunchResponse>(body, "launch an instance").unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.starts_with("Failed to parse response (launch an instance)"));
+        assert!(msg.contains("i-123"));
+        assert!(msg.contains("[REDACTED]"));
+        assert!(!msg.contains("hunter2"));
+
+        let launched: LaunchResponse =
+            parse_api_response(r#"{"data": {"instance_ids": ["i-1"]}}"#, "launch").unwrap();
+        assert_eq!(launched.instance_ids, vec!["i-1"]);
+
+        let terminated: TerminateResponse = parse_api_response(
+            r#"{"data": {"terminated_instances": [{"id": "i-1", "status": "terminated"}]}}"#,
+            "terminate instances",
+        )
+        .unwrap();
+        assert_eq!(
+            terminated.terminated_instances[0].id.as_deref(),
+            Some("i-1")
+        );
+    }
+
+    #[test]
+    fn test_body_snippet_truncates() {
+        assert_eq!(body_snippet(""), "<empty>");
+        assert_eq!(
+            body_snippet("<html>\n  Bad Gateway\n</html>"),
+            "<html> Bad Gateway </html>"
+        );
+
+        let long = "x".repeat(500);
+        let snippet = body_snippet(&long);
+        assert_eq!(snippet.len(), BODY_SNIPPET_LEN + 3);
+        assert!(snippet.ends_with("..."));
+    }
+
+    #[test]
+    fn test_parse_header_list() {
+        assert_eq!(
+            parse_header_list("X-Request-Id=abc, X-Proxy-Auth = t=1,").unwrap(),
+            vec![("X-Request-Id", "abc"), ("X-Proxy-Auth ", " t=1")]
+        );
+        assert!(parse_header_list("").unwrap().is_empty());
+        assert!(parse_header_list("missing-value").is_err());
+
+        let client = LambdaClient::new("key".to_string())
+            .unwrap()
+            .with_header("X-Proxy-Auth ", " t=1")
+            .unwrap();
+        assert_eq!(client.extra_headers["x-proxy-auth"], "t=1");
+        assert!(LambdaClient::new("key".to_string())
+            .unwrap()
+            .with_header("bad header", "v")
+            .is_err());
+    }
+
+    #[test]
+    fn test_read_api_key() {
+        assert_eq!(
+            read_api_key("  secret_abc \nignored\n".as_bytes()).unwrap(),
+            "secret_abc"
+        );
+        assert!(read_api_key("".as_bytes()).is_err());
+        assert!(read_api_key("\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_normalize_region() {
+        assert_eq!(normalize_region("us-east-1"), "us-east-1");
+        assert_eq!(normalize_region(" US_East_1 "), "us-east-1");
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(45_900)), "45s");
+        assert_eq!(format_elapsed(Duration::from_secs(134)), "2m14s");
+        assert_eq!(format_elapsed(Duration::from_secs(3900)), "1h05m");
+    }
+
+    #[test]
+    fn test_env_reference() {
+        assert_eq!(env_reference("$LAMBDA_WORK_KEY"), Some("LAMBDA_WORK_KEY"));
+        assert_eq!(env_reference(" $_KEY2 "), Some("_KEY2"));
+        assert_eq!(env_reference("secret_abc"), None);
+        assert_eq!(env_reference("$"), None);
+        assert_eq!(env_reference("$2KEY"), None);
+        assert_eq!(env_reference("$(op read x)"), None);
+        assert_eq!(env_reference("op read $VAULT_PATH"), None);
+
+        assert_eq!(
+            resolve_env_reference("LAMBDA_API_KEY", "secret_abc".to_string()).unwrap(),
+            "secret_abc"
+        );
+        assert!(
+            resolve_env_reference("LAMBDA_API_KEY", "$LAMBDA_CLI_TEST_UNSET_KEY".to_string())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_price_as_number_or_string() {
+        let parse = |price: serde_json::Value| {
+            serde_json::from_value::<InstanceType>(serde_json::json!({
+                "description": "1x A10",
+                "price_cents_per_hour": price,
+                "specs": { "vcpus": 30, "memory_gib": 200, "storage_gib": 1400 }
+            }))
+        };
+
+        assert_eq!(
+            parse(serde_json::json!(75)).unwrap().price_cents_per_hour,
+            75
+        );
+        assert_eq!(
+            parse(serde_json::json!("75")).unwrap().price_cents_per_hour,
+            75
+        );
+        assert!(parse(serde_json::json!("cheap")).is_err());
+        assert!(parse(serde_json::json!(null)).is_err());
+    }
+
+    #[test]
+    fn test_filesystem_region() {
+        let filesystems: Vec<Filesystem> = serde_json::from_value(serde_json::json!([{
+            "id": "fs-1",
+            "name": "data",
+            "mount_point": "/lambda/nfs/data",
+            "created": "2025-01-01T00:00:00Z",
+            "region": { "name": "us-east-1", "description": "Virginia" },
+            "is_in_use": false
+        }]))
+        .unwrap();
+
+        assert_eq!(
+            filesystem_region(&filesystems, "data").unwrap(),
+            "us-east-1"
+        );
+
+        let err = filesystem_region(&filesystems, "dta").unwrap_err();
+        assert_eq!(err.kind(), "filesystem_not_found");
+        assert_eq!(
+            err.to_string(),
+            "Filesystem 'dta' not found. Available filesystems: data"
+        );
+        assert!(filesystem_region(&[], "data")
+            .unwrap_err()
+            .to_string()
+            .ends_with("Available filesystems: none"));
+    }
+
+    #[test]
+    fn test_instance_status_reason() {
+        let instance: Instance = serde_json::from_str(
+            r#"{"id": "i-1", "status": "unhealthy", "status_detail": "host unreachable"}"#,
+        )
+        .unwrap();
+        assert_eq!(instance.status_display(), "unhealthy (host unreachable)");
+
+        let instance: Instance =
+            serde_json::from_str(r#"{"id": "i-1", "status": "active"}"#).unwrap();
+        assert!(instance.status_reason.is_none());
+        assert_eq!(instance.status_display(), "active");
+    }
+
+    #[test]
+    fn test_instance_status() {
+        let instance: Instance =
+            serde_json::from_str(r#"{"id": "i-1", "status": "terminated"}"#).unwrap();
+        assert_eq!(instance.status, Some(InstanceStatus::Terminated));
+        assert!(instance.status.as_ref().unwrap().is_failed());
+
+        let instance: Instance =
+            serde_json::from_str(r#"{"id": "i-1", "status": "terminating"}"#).unwrap();
+        assert_eq!(
+            instance.status,
+            Some(InstanceStatus::Unknown("terminating".to_string()))
+        );
+        assert_eq!(
+            serde_json::to_value(&instance).unwrap()["status"],
+            "terminating"
+        );
+        assert!(!InstanceStatus::Booting.is_failed());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_api_key_ttl_and_refresh() {
+        // Each run of the command prints how many times it has run
+        let counter = std::env::temp_dir().join(format!("lambda-key-ttl-{}", std::process::id()));
+        let command = format!("echo x >> {0}; wc -l < {0}", counter.display());
+
+        let client = LambdaClient::new_lazy(ApiKeySource::Command(command.clone())).unwrap();
+        assert_eq!(client.get_api_key().unwrap(), "1");
+        assert_eq!(client.get_api_key().unwrap(), "1");
+        client.refresh_api_key().unwrap();
+        assert_eq!(client.get_api_key().unwrap(), "2");
+
+        // An operation view shares the key cache but spends its own retry budget
+        let client = client.with_retry_budget(1, Duration::from_secs(60));
+        let view = client.for_operation();
+        assert_eq!(view.get_api_key().unwrap(), "2");
+        assert!(client.retry_budget.acquire(None).is_some());
+        assert!(client.retry_budget.acquire(None).is_none());
+        assert!(view.retry_budget.acquire(None).is_some());
+
+        let client = LambdaClient::new_lazy(ApiKeySource::Command(command))
+            .unwrap()
+            .with_api_key_ttl(Duration::ZERO);
+        assert_eq!(client.get_api_key().unwrap(), "3");
+        assert_eq!(client.get_api_key().unwrap(), "4");
+
+        std::fs::remove_file(counter).ok();
+
+        // A lazy client without a key fails per request rather than at startup
+        let client = LambdaClient::new_lazy(ApiKeySource::Missing).unwrap();
+        let err = client.get_api_key().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<LambdaError>(),
+            Some(LambdaError::ApiKeyNotSet)
+        ));
+        client.refresh_api_key().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_header_command() {
+        let counter =
+            std::env::temp_dir().join(format!("lambda-header-cmd-{}", std::process::id()));
+        let command = format!(
+            "echo x >> {0}; echo token-$(wc -l < {0})",
+            counter.display()
+        );
+
+        let client = LambdaClient::new("key".to_string())
+            .unwrap()
+            .with_header_command("X-Gateway-Token", &command, None)
+            .unwrap();
+        let request = client
+            .build_request(Method::GET, "/instances")
+            .build()
+            .unwrap();
+        let request = client.authorize(request).unwrap();
+        assert_eq!(request.headers()["x-gateway-token"], "token-1");
+        let header = client.command_header.as_ref().unwrap();
+        assert_eq!(header.value().unwrap(), "token-1");
+
+        let failing = LambdaClient::new("key".to_string())
+            .unwrap()
+            .with_header_command("X-Gateway-Token", "exit 1", None)
+            .unwrap();
+        let err = failing
+            .command_header
+            .as_ref()
+            .unwrap()
+            .value()
+            .unwrap_err();
+        assert!(err.to_string().contains("x-gateway-token"));
+
+        std::fs::remove_file(counter).ok();
+    }
+
     #[test]
     fn test_api_base_url() {
         assert_eq!(API_BASE_URL, "https://cloud.lambdalabs.com/api/v1");
@@ -1,20 +1,39 @@
-use anyhow::Result;
-use lambda_cli::api::{Filesystem, Instance, InstanceTypeData, LambdaClient};
-use lambda_cli::notify::{InstanceReadyMessage, Notifier, NotifyConfig};
+use anyhow::{Context, Result};
+use lambda_cli::api::{
+    format_elapsed, normalize_region, ssh_command, Filesystem, Instance, InstanceStatus,
+    InstanceTypeData, LambdaClient, LambdaError, LaunchOptions, LaunchResult,
+};
+use lambda_cli::cache::TtlCache;
+use lambda_cli::cost::{estimate_fleet_cost, FleetCost};
+use lambda_cli::env_config;
+use lambda_cli::gpu_alias::GpuAliases;
+use lambda_cli::notify::{self, ChannelStatus, InstanceReadyMessage, Notifier, NotifyConfig};
 use rmcp::handler::server::tool::ToolRouter;
 use rmcp::handler::server::wrapper::Parameters;
 use rmcp::model::{CallToolResult, Content, ServerCapabilities, ServerInfo};
 use rmcp::schemars::JsonSchema;
 use rmcp::serde::Deserialize;
 use rmcp::{tool, tool_handler, tool_router, ErrorData as McpError, ServerHandler, ServiceExt};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::task::AbortHandle;
 
 /// Lambda MCP Server
 #[derive(Clone)]
 struct LambdaService {
     client: Arc<LambdaClient>,
     notify_config: Option<NotifyConfig>,
+    /// How long the background notifier waits for an instance to get an IP
+    notify_max_wait: Duration,
+    /// Short GPU names from `LAMBDA_GPU_ALIASES`
+    gpu_aliases: GpuAliases,
+    /// Recent list responses, so chatty agents don't hit the API on every call
+    instance_types_cache: Arc<TtlCache<Vec<InstanceTypeData>>>,
+    instances_cache: Arc<TtlCache<Vec<Instance>>>,
+    filesystems_cache: Arc<TtlCache<Vec<Filesystem>>>,
+    /// Background ready notifications still waiting, by instance ID
+    notify_tasks: Arc<Mutex<HashMap<String, AbortHandle>>>,
     #[allow(dead_code)]
     tool_router: ToolRouter<Self>,
 }
@@ -22,8 +41,13 @@ struct LambdaService {
 impl LambdaService {
     fn new(lazy: bool) -> Result<Self> {
         dotenv::dotenv().ok();
+        // Individual env vars (and .env) take precedence over the JSON blob
+        env_config::apply()?;
         let client = LambdaClient::from_env_with_options(lazy)?;
         let notify_config = NotifyConfig::from_env();
+        let notify_max_wait = notify_max_wait_from_env()?;
+        let gpu_aliases = GpuAliases::from_env()?;
+        let cache_ttl = cache_ttl_from_env()?;
 
         // Debug: log notification config status
         if let Some(ref config) = notify_config {
@@ -38,10 +62,203 @@ impl LambdaService {
         Ok(Self {
             client: Arc::new(client),
             notify_config,
+            notify_max_wait,
+            gpu_aliases,
+            instance_types_cache: Arc::new(TtlCache::new(cache_ttl)),
+            instances_cache: Arc::new(TtlCache::new(cache_ttl)),
+            filesystems_cache: Arc::new(TtlCache::new(cache_ttl)),
+            notify_tasks: Arc::default(),
             tool_router: Self::tool_router(),
         })
     }
 
+    /// Shared body of `attach_filesystem` and `detach_filesystem`
+    async fn relaunch(
+        &self,
+        client: &LambdaClient,
+        instance_id: &str,
+        filesystem: Option<&str>,
+        confirm: bool,
+    ) -> Result<CallToolResult, McpError> {
+        let change = match filesystem {
+            Some(fs) => format!("attach filesystem {}", fs),
+            None => "detach its filesystem".to_string(),
+        };
+        if !confirm {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Refusing to {} on instance {} without confirmation. \
+                     Filesystems can only be set at launch, so this terminates the instance \
+                     and launches a replacement with a new ID and IP; anything on its local \
+                     disk (outside filesystems) is lost. Call again with confirm: true if the \
+                     user agreed to this.",
+                    change, instance_id
+                ),
+                None,
+            ));
+        }
+
+        let result = client
+            .relaunch_with_filesystem(instance_id, filesystem)
+            .await;
+        self.invalidate_after_launch();
+        let (result, spec) = result.map_err(tool_error)?;
+        self.cancel_ready_notification(instance_id);
+
+        let mut text = format!(
+            "Replaced instance {} to {}.\n\nNew instance ID: {}\nRegion: {}\n\nThe original was terminated. Use 'list_running_instances' to get the new IP once it boots.",
+            instance_id, change, result.instance_id, result.region
+        );
+        let dropped = spec.dropped_ssh_keys();
+        if !dropped.is_empty() {
+            text.push_str(&format!(
+                "\n\nWarning: a launch takes one SSH key, so the replacement only has '{}'. These keys from the original were not added: {}",
+                spec.ssh_key().unwrap_or_default(),
+                dropped.join(", ")
+            ));
+        }
+        if let Some(channels) =
+            self.spawn_ready_notification(&result, &spec.instance_type, spec.name.as_deref())
+        {
+            text.push_str(&format!(
+                "\n\nNotifications enabled for: {}. You will be notified when the replacement is SSH-able.",
+                channels
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    /// Forget the running list, and the filesystems whose `is_in_use` follows it
+    fn invalidate_instances(&self) {
+        self.instances_cache.invalidate();
+        self.filesystems_cache.invalidate();
+    }
+
+    /// A launch also changes the remaining capacity
+    fn invalidate_after_launch(&self) {
+        self.invalidate_instances();
+        self.instance_types_cache.invalidate();
+    }
+
+    /// Notify in the background once a launched instance is SSH-able.
+    ///
+    /// Returns the channels that will be notified, or `None` if none are configured.
+    fn spawn_ready_notification(
+        &self,
+        launched: &LaunchResult,
+        gpu_type: &str,
+        instance_name: Option<&str>,
+    ) -> Option<String> {
+        let config = self.notify_config.as_ref()?;
+        let client = Arc::clone(&self.client);
+        let notifier =
+            Notifier::new(config.clone()).with_http_client(self.client.http_client().clone());
+        let instance_id = launched.instance_id.clone();
+        let instance_name = instance_name.map(String::from);
+        let gpu_type = gpu_type.to_string();
+        let region = launched.region.clone();
+        let max_wait = self.notify_max_wait;
+        let tasks = Arc::clone(&self.notify_tasks);
+
+        // Hold the lock across the spawn so the task can't remove itself before it's recorded
+        let mut pending = self.notify_tasks.lock().ok()?;
+        let task = tokio::spawn(async move {
+            poll_and_notify(
+                client,
+                notifier,
+                instance_id.clone(),
+                instance_name,
+                gpu_type,
+                region,
+                max_wait,
+            )
+            .await;
+            if let Ok(mut tasks) = tasks.lock() {
+                tasks.remove(&instance_id);
+            }
+        });
+        pending.insert(launched.instance_id.clone(), task.abort_handle());
+
+        Some(config.configured_channels().join(", "))
+    }
+
+    /// Stop waiting to notify about an instance that was deliberately terminated
+    fn cancel_ready_notification(&self, instance_id: &str) {
+        let task = self
+            .notify_tasks
+            .lock()
+            .ok()
+            .and_then(|mut tasks| tasks.remove(instance_id));
+        if let Some(task) = task {
+            task.abort();
+            eprintln!(
+                "[lambda-mcp] Cancelled ready notification for terminated instance {}",
+                instance_id
+            );
+        }
+    }
+
+    /// `start_instance` with `count` > 1: launch one at a time, stopping at the first failure
+    async fn start_many(
+        &self,
+        client: &LambdaClient,
+        params: &StartInstanceParams,
+        options: &LaunchOptions,
+        count: u32,
+    ) -> Result<CallToolResult, McpError> {
+        let mut launched = Vec::new();
+        let mut failure = None;
+        for _ in 0..count {
+            match client
+                .launch_instance_with_options(&params.gpu, &params.ssh_key, options)
+                .await
+            {
+                Ok(result) => launched.push(result),
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        self.invalidate_after_launch();
+
+        if launched.is_empty() {
+            return Err(failure.map_or_else(
+                || McpError::internal_error("nothing launched", None),
+                tool_error,
+            ));
+        }
+
+        let mut text = format!("Launched {} of {} instance(s):\n\n", launched.len(), count);
+        let mut channels = None;
+        for result in &launched {
+            text.push_str(&format!(
+                "• Instance ID: {} | Region: {}\n",
+                result.instance_id, result.region
+            ));
+            channels = self.spawn_ready_notification(result, &params.gpu, params.name.as_deref());
+        }
+        if let Some(e) = failure {
+            text.push_str(&format!(
+                "\nStopped after a launch failed: {}\nThe instances above are running and billed.\n",
+                e
+            ));
+        }
+        text.push_str("\nInstances may take a few minutes to become active. Use 'list_running_instances' to check status.");
+        if let Some(channels) = channels {
+            text.push_str(&format!(
+                "\n\nNotifications enabled for: {}. You will be notified as each instance becomes SSH-able.",
+                channels
+            ));
+        }
+
+        let ids: Vec<&str> = launched.iter().map(|r| r.instance_id.as_str()).collect();
+        Ok(CallToolResult::success(vec![
+            Content::text(text),
+            Content::json(serde_json::json!({ "instance_ids": ids }))?,
+        ]))
+    }
+
     fn format_instance_types(types: &[InstanceTypeData]) -> String {
         let mut output = String::from("Available GPU Instance Types:\n\n");
         for t in types {
@@ -67,7 +284,7 @@ impl LambdaService {
         for inst in instances {
             let id = inst.id.as_deref().unwrap_or("N/A");
             let name = inst.name.as_deref().unwrap_or("-");
-            let status = inst.status.as_deref().unwrap_or("unknown");
+            let status = inst.status_display();
             let ip = inst.ip.as_deref().unwrap_or("N/A");
             let inst_type = inst
                 .instance_type
@@ -93,6 +310,30 @@ impl LambdaService {
         output
     }
 
+    fn format_fleet_cost(cost: &FleetCost) -> String {
+        if cost.instances.is_empty() {
+            return "No running instances. Current spend is $0.00/hr.".to_string();
+        }
+        let mut output = String::from("Estimated Spend:\n\n");
+        for inst in &cost.instances {
+            let price = match inst.price_cents_per_hour {
+                Some(_) => format!("${:.2}/hr", inst.hourly),
+                None => "unknown price".to_string(),
+            };
+            output.push_str(&format!(
+                "• {} ({}) - {}\n",
+                inst.name.as_deref().unwrap_or(&inst.instance_id),
+                inst.instance_type,
+                price
+            ));
+        }
+        output.push_str(&format!(
+            "\nTotal: ${:.2}/hr | ${:.2}/day | ${:.2}/month\n",
+            cost.hourly, cost.daily, cost.monthly
+        ));
+        output
+    }
+
     fn format_filesystems(filesystems: &[Filesystem]) -> String {
         if filesystems.is_empty() {
             return "No filesystems.".to_string();
@@ -137,6 +378,92 @@ struct StartInstanceParams {
     region: Option<String>,
     /// Optional filesystem name to attach (must be in the same region)
     filesystem: Option<String>,
+    /// Wait (up to 5 minutes) for the instance to become active and include the
+    /// SSH command in the result, instead of returning immediately
+    #[serde(default)]
+    wait_for_ip: bool,
+    /// Number of identical instances to launch (default 1, at most 8); each is billed
+    count: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ListPageParams {
+    /// Only include entries whose name, ID, type, or region contains this text (case-insensitive)
+    filter: Option<String>,
+    /// Number of matching entries to skip (default 0)
+    #[serde(default)]
+    offset: usize,
+    /// Maximum number of entries to return (default: all)
+    limit: Option<usize>,
+}
+
+impl ListPageParams {
+    fn matches(&self, fields: &[Option<&str>]) -> bool {
+        self.filter.as_deref().is_none_or(|filter| {
+            let filter = filter.to_lowercase();
+            fields
+                .iter()
+                .flatten()
+                .any(|field| field.to_lowercase().contains(&filter))
+        })
+    }
+
+    /// Whether the filter names `region`, ignoring `_` vs `-` and case
+    fn matches_region(&self, region: Option<&str>) -> bool {
+        match (self.filter.as_deref(), region) {
+            (Some(filter), Some(region)) => region.contains(&normalize_region(filter)),
+            _ => false,
+        }
+    }
+
+    /// Slice out the requested page, plus a note saying where it sits among the matches
+    fn page<T>(&self, items: Vec<T>) -> (Vec<T>, String) {
+        let total = items.len();
+        if total == 0 {
+            let note = match &self.filter {
+                Some(filter) => format!("Nothing matches filter '{}'.", filter),
+                None => String::new(),
+            };
+            return (items, note);
+        }
+        let page: Vec<T> = items
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        let end = self.offset + page.len();
+        let note = if page.len() == total {
+            String::new()
+        } else if end < total {
+            format!(
+                "Showing {} of {} matching (offset {}). Call again with offset: {} for more.",
+                page.len(),
+                total,
+                self.offset,
+                end
+            )
+        } else {
+            format!(
+                "Showing {} of {} matching (offset {}).",
+                page.len(),
+                total,
+                self.offset
+            )
+        };
+        (page, note)
+    }
+}
+
+/// Append a paging note, if there is one, to a formatted listing
+fn with_page_note(mut text: String, note: &str) -> String {
+    if !note.is_empty() {
+        if !text.ends_with('\n') {
+            text.push_str("\n\n");
+        }
+        text.push_str(note);
+        text.push('\n');
+    }
+    text
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -151,6 +478,12 @@ struct CheckAvailabilityParams {
     gpu: String,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ListFilesystemsParams {
+    /// Optional region to filter by (e.g. the region you plan to launch in)
+    region: Option<String>,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 struct CreateFilesystemParams {
     /// Name for the filesystem
@@ -159,47 +492,102 @@ struct CreateFilesystemParams {
     region: String,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+struct AttachFilesystemParams {
+    /// Instance to replace with one that has the filesystem attached
+    instance_id: String,
+    /// Name of the filesystem to attach (must be in the instance's region)
+    filesystem: String,
+    /// Must be true to confirm; the instance is terminated and its local disk is lost
+    #[serde(default)]
+    confirm: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DetachFilesystemParams {
+    /// Instance to replace with one that has no filesystem attached
+    instance_id: String,
+    /// Must be true to confirm; the instance is terminated and its local disk is lost
+    #[serde(default)]
+    confirm: bool,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 struct DeleteFilesystemParams {
     /// Filesystem ID to delete
     filesystem_id: String,
+    /// Must be true to confirm deletion; filesystem data cannot be recovered
+    #[serde(default)]
+    confirm: bool,
 }
 
 #[tool_router]
 impl LambdaService {
     #[tool(
-        description = "List all available GPU instance types with pricing, specs, and current availability"
+        description = "List available GPU instance types with pricing, specs, and current availability. Use filter (e.g. \"h100\") and limit/offset to keep the result short."
     )]
-    async fn list_gpu_types(&self) -> Result<CallToolResult, McpError> {
+    async fn list_gpu_types(
+        &self,
+        Parameters(params): Parameters<ListPageParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Each tool call gets its own retry budget
+        let client = self.client.for_operation();
         let types = self
-            .client
-            .list_instance_types()
+            .instance_types_cache
+            .get_or_fetch(client.list_instance_types())
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
+
+        let types: Vec<InstanceTypeData> = types
+            .into_iter()
+            .filter(|t| params.matches(&[Some(&t.name), Some(&t.description)]))
+            .collect();
+        let (types, note) = params.page(types);
 
         Ok(CallToolResult::success(vec![Content::text(
-            Self::format_instance_types(&types),
+            with_page_note(Self::format_instance_types(&types), &note),
         )]))
     }
 
     #[tool(
-        description = "Launch a new GPU instance. Returns instance ID and connection details. Optionally attach a filesystem (must be in the same region). If notification env vars are configured, will auto-notify when instance is SSH-able."
+        description = "Launch a new GPU instance. Returns instance ID and connection details. Optionally attach a filesystem (must be in the same region). If notification env vars are configured, will auto-notify when instance is SSH-able. Set wait_for_ip to block until the instance is active (up to 5 minutes) and get the SSH command in the result. Set count (up to 8) to launch several identical instances; each is billed."
     )]
     async fn start_instance(
         &self,
-        Parameters(params): Parameters<StartInstanceParams>,
+        Parameters(mut params): Parameters<StartInstanceParams>,
     ) -> Result<CallToolResult, McpError> {
-        let result = self
-            .client
-            .launch_instance_with_filesystem(
-                &params.gpu,
-                &params.ssh_key,
-                params.name.as_deref(),
-                params.region.as_deref(),
-                params.filesystem.as_deref(),
-            )
-            .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let client = self.client.for_operation();
+        params.gpu = self.gpu_aliases.resolve(&params.gpu).to_string();
+        let options = LaunchOptions {
+            name: params.name.clone(),
+            region: params.region.clone(),
+            filesystem: params.filesystem.clone(),
+            ..LaunchOptions::default()
+        };
+
+        if client.requires_name() && params.name.as_deref().is_none_or(str::is_empty) {
+            return Err(McpError::invalid_params(
+                "name is required: LAMBDA_REQUIRE_NAME is set so every instance is attributable",
+                None,
+            ));
+        }
+
+        let count = params.count.unwrap_or(1);
+        if count == 0 || count > MAX_START_COUNT {
+            return Err(McpError::invalid_params(
+                format!("count must be between 1 and {}", MAX_START_COUNT),
+                None,
+            ));
+        }
+        if count > 1 {
+            if params.wait_for_ip {
+                return Err(McpError::invalid_params(
+                    "wait_for_ip only supports launching one instance; omit it and use 'list_running_instances' to check on the fleet",
+                    None,
+                ));
+            }
+            return self.start_many(&client, &params, &options, count).await;
+        }
 
         let fs_info = params
             .filesystem
@@ -207,31 +595,84 @@ impl LambdaService {
             .map(|f| format!("\nFilesystem: {} (mounted at /lambda/nfs/{})", f, f))
             .unwrap_or_default();
 
-        // Spawn background task to notify when instance is ready
-        let notify_status = if let Some(ref config) = self.notify_config {
-            let channels = config.configured_channels().join(", ");
-            let client = Arc::clone(&self.client);
-            let notifier = Notifier::new(config.clone());
-            let instance_id = result.instance_id.clone();
-            let instance_name = params.name.clone();
-            let gpu_type = params.gpu.clone();
-            let region = result.region.clone();
-
-            tokio::spawn(async move {
-                poll_and_notify(
-                    client,
-                    notifier,
-                    instance_id,
-                    instance_name,
-                    gpu_type,
-                    region,
+        if params.wait_for_ip {
+            let launched_at = std::time::Instant::now();
+            let ready = client
+                .launch_and_wait(
+                    &params.gpu,
+                    &params.ssh_key,
+                    &options,
+                    POLL_INTERVAL,
+                    WAIT_FOR_IP_TIMEOUT,
                 )
                 .await;
-            });
+            self.invalidate_after_launch();
+            let text = match ready {
+                Ok(instance) => {
+                    let instance_id = instance.id.clone().unwrap_or_default();
+                    let ip = instance.ip.clone().unwrap_or_default();
+                    let ready_after = launched_at.elapsed();
+                    let region = instance.region.unwrap_or_default();
+                    let region_name = region.name.unwrap_or_default();
+                    let mut notify_warning = "";
+                    if let Some(ref config) = self.notify_config {
+                        let msg = InstanceReadyMessage {
+                            instance_id: instance_id.clone(),
+                            instance_name: params.name.clone(),
+                            ip: ip.clone(),
+                            gpu_type: params.gpu.clone(),
+                            region: region_name.clone(),
+                            region_description: region.description,
+                            filesystems: instance.file_system_names.unwrap_or_default(),
+                            ready_after: Some(ready_after),
+                        };
+                        let results = Notifier::new(config.clone())
+                            .with_http_client(client.http_client().clone())
+                            .send_all(&msg)
+                            .await;
+                        if !log_notify_results(&instance_id, results) {
+                            notify_warning = "\n\nWarning: notifications failed on all configured channels.";
+                        }
+                    }
+                    format!(
+                        "Instance launched and reachable in {}!\n\nInstance ID: {}\nRegion: {}{}\nIP: {}\nSSH: {}{}",
+                        format_elapsed(ready_after),
+                        instance_id,
+                        region_name,
+                        fs_info,
+                        ip,
+                        ssh_command(&ip),
+                        notify_warning
+                    )
+                }
+                Err(e) => match e.downcast_ref::<LambdaError>() {
+                    Some(LambdaError::LaunchedNotReady {
+                        instance_id,
+                        region,
+                        reason,
+                    }) => format!(
+                        "Instance launched, but it is not reachable yet: {}\n\nInstance ID: {}\nRegion: {}{}\n\nUse 'list_running_instances' to check status.",
+                        reason, instance_id, region, fs_info
+                    ),
+                    _ => return Err(tool_error(e)),
+                },
+            };
+            return Ok(CallToolResult::success(vec![Content::text(text)]));
+        }
 
-            format!("\n\nNotifications enabled for: {}. You will be notified when the instance is SSH-able.", channels)
-        } else {
-            String::new()
+        let result = client
+            .launch_instance_with_options(&params.gpu, &params.ssh_key, &options)
+            .await;
+        self.invalidate_after_launch();
+        let result = result.map_err(tool_error)?;
+
+        let notify_status = match self.spawn_ready_notification(
+            &result,
+            &params.gpu,
+            params.name.as_deref(),
+        ) {
+            Some(channels) => format!("\n\nNotifications enabled for: {}. You will be notified when the instance is SSH-able.", channels),
+            None => String::new(),
         };
 
         Ok(CallToolResult::success(vec![Content::text(format!(
@@ -245,10 +686,11 @@ impl LambdaService {
         &self,
         Parameters(params): Parameters<StopInstanceParams>,
     ) -> Result<CallToolResult, McpError> {
-        self.client
-            .terminate_instance(&params.instance_id)
-            .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let client = self.client.for_operation();
+        let result = client.terminate_instance(&params.instance_id).await;
+        self.invalidate_instances();
+        result.map_err(tool_error)?;
+        self.cancel_ready_notification(&params.instance_id);
 
         Ok(CallToolResult::success(vec![Content::text(format!(
             "Instance {} terminated successfully.",
@@ -257,32 +699,99 @@ impl LambdaService {
     }
 
     #[tool(
-        description = "List all currently running GPU instances with their status and connection details"
+        description = "List currently running GPU instances with their status and connection details. Use filter (matches ID, name, type, or region) and limit/offset to page through large accounts."
     )]
-    async fn list_running_instances(&self) -> Result<CallToolResult, McpError> {
+    async fn list_running_instances(
+        &self,
+        Parameters(params): Parameters<ListPageParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let client = self.client.for_operation();
         let instances = self
-            .client
-            .list_running_instances()
+            .instances_cache
+            .get_or_fetch(client.list_running_instances())
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
+
+        let instances: Vec<Instance> = instances
+            .into_iter()
+            .filter(|inst| {
+                let region = inst.region.as_ref().and_then(|r| r.name.as_deref());
+                params.matches(&[
+                    inst.id.as_deref(),
+                    inst.name.as_deref(),
+                    inst.type_name(),
+                    region,
+                ]) || params.matches_region(region)
+            })
+            .collect();
+        let (instances, note) = params.page(instances);
 
         Ok(CallToolResult::success(vec![Content::text(
-            Self::format_instances(&instances),
+            with_page_note(Self::format_instances(&instances), &note),
         )]))
     }
 
     #[tool(
-        description = "Check if a specific GPU type is currently available. Returns list of regions with availability."
+        description = "Show which notification channels (Slack, Discord, Telegram) are configured to fire when instances become ready, without sending anything. Secrets are not included. Also returns JSON [{channel, configured, detail}]."
+    )]
+    async fn notify_status(&self) -> Result<CallToolResult, McpError> {
+        let status = ChannelStatus::from_env();
+        let mut text = String::from("Notification Channels:\n\n");
+        for channel in &status {
+            text.push_str(&format!(
+                "• {}: {} ({})\n",
+                channel.channel,
+                if channel.configured {
+                    "configured"
+                } else {
+                    "not configured"
+                },
+                channel.detail
+            ));
+        }
+
+        Ok(CallToolResult::success(vec![
+            Content::text(text),
+            Content::json(&status)?,
+        ]))
+    }
+
+    #[tool(
+        description = "Estimate the current hourly, daily, and monthly spend across all running instances, with per-instance figures"
+    )]
+    async fn estimate_cost(&self) -> Result<CallToolResult, McpError> {
+        let client = self.client.for_operation();
+        let (instances, types) = tokio::try_join!(
+            self.instances_cache
+                .get_or_fetch(client.list_running_instances()),
+            self.instance_types_cache
+                .get_or_fetch(client.list_instance_types())
+        )
+        .map_err(tool_error)?;
+        let types: HashMap<String, InstanceTypeData> =
+            types.into_iter().map(|t| (t.name.clone(), t)).collect();
+
+        let cost = estimate_fleet_cost(&instances, &types);
+
+        Ok(CallToolResult::success(vec![
+            Content::text(Self::format_fleet_cost(&cost)),
+            Content::json(&cost)?,
+        ]))
+    }
+
+    #[tool(
+        description = "Check if a specific GPU type is currently available. Returns list of regions with availability, plus JSON {gpu, available, regions}."
     )]
     async fn check_availability(
         &self,
-        Parameters(params): Parameters<CheckAvailabilityParams>,
+        Parameters(mut params): Parameters<CheckAvailabilityParams>,
     ) -> Result<CallToolResult, McpError> {
-        let regions = self
-            .client
+        let client = self.client.for_operation();
+        params.gpu = self.gpu_aliases.resolve(&params.gpu).to_string();
+        let regions = client
             .check_availability(&params.gpu)
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
 
         let message = if regions.is_empty() {
             format!(
@@ -297,18 +806,33 @@ impl LambdaService {
             )
         };
 
-        Ok(CallToolResult::success(vec![Content::text(message)]))
+        Ok(CallToolResult::success(vec![
+            Content::text(message),
+            Content::json(serde_json::json!({
+                "gpu": params.gpu,
+                "available": !regions.is_empty(),
+                "regions": regions,
+            }))?,
+        ]))
     }
 
     #[tool(
-        description = "List all filesystems (persistent storage). Filesystems can be attached to instances at launch time."
+        description = "List all filesystems (persistent storage). Filesystems can be attached to instances at launch time. Pass a region to see only filesystems usable with instances in that region."
     )]
-    async fn list_filesystems(&self) -> Result<CallToolResult, McpError> {
-        let filesystems = self
-            .client
-            .list_filesystems()
+    async fn list_filesystems(
+        &self,
+        Parameters(params): Parameters<ListFilesystemsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let client = self.client.for_operation();
+        let mut filesystems = self
+            .filesystems_cache
+            .get_or_fetch(client.list_filesystems())
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
+        if let Some(ref region) = params.region {
+            let region = normalize_region(region);
+            filesystems.retain(|fs| fs.region.name == region);
+        }
 
         Ok(CallToolResult::success(vec![Content::text(
             Self::format_filesystems(&filesystems),
@@ -322,11 +846,10 @@ impl LambdaService {
         &self,
         Parameters(params): Parameters<CreateFilesystemParams>,
     ) -> Result<CallToolResult, McpError> {
-        let fs = self
-            .client
-            .create_filesystem(&params.name, &params.region)
-            .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let client = self.client.for_operation();
+        let fs = client.create_filesystem(&params.name, &params.region).await;
+        self.filesystems_cache.invalidate();
+        let fs = fs.map_err(tool_error)?;
 
         Ok(CallToolResult::success(vec![Content::text(format!(
             "Filesystem created successfully!\n\nName: {}\nID: {}\nRegion: {}\nMount point: {}",
@@ -334,15 +857,58 @@ impl LambdaService {
         ))]))
     }
 
-    #[tool(description = "Delete a filesystem. The filesystem must not be in use by any instance.")]
+    #[tool(
+        description = "Attach a filesystem to a running instance. Lambda only attaches filesystems at launch, so this launches a replacement instance (same type, region, first SSH key, and name) with the filesystem, then terminates the original. The original's local disk is lost and the replacement has a new ID and IP. Requires confirm: true; only set it when the user has agreed to this."
+    )]
+    async fn attach_filesystem(
+        &self,
+        Parameters(params): Parameters<AttachFilesystemParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let client = self.client.for_operation();
+        self.relaunch(
+            &client,
+            &params.instance_id,
+            Some(&params.filesystem),
+            params.confirm,
+        )
+        .await
+    }
+
+    #[tool(
+        description = "Detach a filesystem from a running instance. Lambda only sets filesystems at launch, so this launches a replacement instance (same type, region, first SSH key, and name) without a filesystem, then terminates the original. The original's local disk is lost and the replacement has a new ID and IP. Requires confirm: true; only set it when the user has agreed to this."
+    )]
+    async fn detach_filesystem(
+        &self,
+        Parameters(params): Parameters<DetachFilesystemParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let client = self.client.for_operation();
+        self.relaunch(&client, &params.instance_id, None, params.confirm)
+            .await
+    }
+
+    #[tool(
+        description = "Permanently delete a filesystem and all data on it. The filesystem must not be in use by any instance. Requires confirm: true; only set it when the user has explicitly asked to delete this filesystem."
+    )]
     async fn delete_filesystem(
         &self,
         Parameters(params): Parameters<DeleteFilesystemParams>,
     ) -> Result<CallToolResult, McpError> {
-        self.client
-            .delete_filesystem(&params.filesystem_id)
-            .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let client = self.client.for_operation();
+        if !params.confirm {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Refusing to delete filesystem {} without confirmation. \
+                     Deleting a filesystem permanently destroys its data; \
+                     call again with confirm: true if the user explicitly asked for this.",
+                    params.filesystem_id
+                ),
+                None,
+            ));
+        }
+
+        let result = client.delete_filesystem(&params.filesystem_id).await;
+        self.filesystems_cache.invalidate();
+        result.map_err(tool_error)?;
 
         Ok(CallToolResult::success(vec![Content::text(format!(
             "Filesystem {} deleted successfully.",
@@ -368,76 +934,181 @@ impl ServerHandler for LambdaService {
     }
 }
 
-/// Background task to poll for instance readiness and send notifications
-async fn poll_and_notify(
-    client: Arc<LambdaClient>,
-    notifier: Notifier,
-    instance_id: String,
-    instance_name: Option<String>,
-    gpu_type: String,
-    region: String,
-) {
-    let max_wait = Duration::from_secs(600); // 10 minutes max
-    let poll_interval = Duration::from_secs(10);
+/// Map a client error to a tool error, tagged with its `LambdaError::kind()` if it has one.
+///
+/// Key errors first surface on the first tool call (the key is loaded lazily),
+/// so they say what to fix: the server's env config, or the key command itself.
+fn tool_error(e: anyhow::Error) -> McpError {
+    let Some(err) = e.downcast_ref::<LambdaError>() else {
+        return McpError::internal_error(e.to_string(), None);
+    };
+    let message = match err {
+        LambdaError::ApiKeyNotSet => "No API key is configured. Set LAMBDA_API_KEY, or \
+             LAMBDA_API_KEY_COMMAND to a command that prints the key, in this MCP server's \
+             env config, then restart the server."
+            .to_string(),
+        LambdaError::ApiKeyCommandFailed(detail) => format!(
+            "LAMBDA_API_KEY_COMMAND is configured but failed: {}. Check that the command \
+             prints the key when run in a shell (e.g. that the password manager is signed \
+             in); it runs again on the next tool call, so no restart is needed.",
+            detail
+        ),
+        _ => e.to_string(),
+    };
+    McpError::internal_error(message, Some(serde_json::json!({ "kind": err.kind() })))
+}
+
+/// Most instances one `start_instance` call may launch
+const MAX_START_COUNT: u32 = 8;
+/// How long `start_instance` waits for the instance to become active when `wait_for_ip` is set
+const WAIT_FOR_IP_TIMEOUT: Duration = Duration::from_secs(300);
+/// Default for `LAMBDA_MCP_CACHE_TTL`
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(5);
+/// Default for `LAMBDA_NOTIFY_MAX_WAIT`
+const DEFAULT_NOTIFY_MAX_WAIT: Duration = Duration::from_secs(600);
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// List cache lifetime from `LAMBDA_MCP_CACHE_TTL` (seconds, 0 disables)
+fn cache_ttl_from_env() -> Result<Duration> {
+    match std::env::var("LAMBDA_MCP_CACHE_TTL") {
+        Ok(secs) if !secs.is_empty() => {
+            let secs: u64 = secs.trim().parse().with_context(|| {
+                format!("Invalid LAMBDA_MCP_CACHE_TTL '{}' (expected seconds)", secs)
+            })?;
+            Ok(Duration::from_secs(secs))
+        }
+        _ => Ok(DEFAULT_CACHE_TTL),
+    }
+}
+
+/// Background notifier wait from `LAMBDA_NOTIFY_MAX_WAIT` (seconds)
+fn notify_max_wait_from_env() -> Result<Duration> {
+    match std::env::var("LAMBDA_NOTIFY_MAX_WAIT") {
+        Ok(secs) if !secs.is_empty() => {
+            let secs: u64 = secs.trim().parse().with_context(|| {
+                format!(
+                    "Invalid LAMBDA_NOTIFY_MAX_WAIT '{}' (expected seconds)",
+                    secs
+                )
+            })?;
+            Ok(Duration::from_secs(secs))
+        }
+        _ => Ok(DEFAULT_NOTIFY_MAX_WAIT),
+    }
+}
+
+/// Poll until the instance has an IP (it may not be "active" yet)
+async fn wait_for_ip(
+    client: &LambdaClient,
+    instance_id: &str,
+    max_wait: Duration,
+) -> Result<Instance> {
     let start = std::time::Instant::now();
 
     loop {
         if start.elapsed() > max_wait {
-            eprintln!(
-                "[notify] Timeout waiting for instance {} to become active",
+            anyhow::bail!(
+                "no IP after {} seconds waiting for instance {}",
+                max_wait.as_secs(),
                 instance_id
             );
-            return;
         }
 
-        tokio::time::sleep(poll_interval).await;
+        tokio::time::sleep(POLL_INTERVAL).await;
 
-        match client.get_instance(&instance_id).await {
+        // Each poll gets its own retry budget, apart from any tool call in flight
+        match client.for_operation().get_instance(instance_id).await {
             Ok(instance) => {
-                let status = instance.status.as_deref().unwrap_or("unknown");
-
-                if status == "terminated" || status == "unhealthy" {
-                    eprintln!(
-                        "[notify] Instance {} entered {} state, stopping notifications",
-                        instance_id, status
+                if instance
+                    .status
+                    .as_ref()
+                    .is_some_and(InstanceStatus::is_failed)
+                {
+                    anyhow::bail!(
+                        "instance {} entered {} state",
+                        instance_id,
+                        instance.status_display()
                     );
-                    return;
                 }
 
-                // Notify when IP is available (don't wait for "active" status)
-                if let Some(ip) = instance.ip {
-                    let msg = InstanceReadyMessage {
-                        instance_id: instance_id.clone(),
-                        instance_name,
-                        ip,
-                        gpu_type,
-                        region,
-                    };
-
-                    let results = notifier.send_all(&msg).await;
-                    for (channel, result) in results {
-                        match result {
-                            Ok(()) => eprintln!(
-                                "[notify] {} notification sent for {}",
-                                channel, instance_id
-                            ),
-                            Err(e) => {
-                                eprintln!("[notify] {} notification failed: {}", channel, e)
-                            }
-                        }
-                    }
-                    return;
+                if instance.ip.is_some() {
+                    return Ok(instance);
                 }
                 // No IP yet, continue polling
             }
             Err(e) => {
-                eprintln!("[notify] Error checking instance {}: {}", instance_id, e);
+                eprintln!("[poll] Error checking instance {}: {}", instance_id, e);
                 // Continue polling on transient errors
             }
         }
     }
 }
 
+/// Log each channel's result and a summary; returns `false` if every channel failed
+fn log_notify_results(instance_id: &str, results: Vec<(&'static str, Result<()>)>) -> bool {
+    let (succeeded, failed) = notify::summarize(&results);
+    for (channel, result) in results {
+        match result {
+            Ok(()) => eprintln!("[notify] {} notification sent for {}", channel, instance_id),
+            Err(e) => eprintln!("[notify] {} notification failed: {}", channel, e),
+        }
+    }
+
+    if succeeded + failed > 0 {
+        eprintln!(
+            "[notify] {}/{} channels notified for {}",
+            succeeded,
+            succeeded + failed,
+            instance_id
+        );
+    }
+    succeeded > 0 || failed == 0
+}
+
+/// Background task to poll for instance readiness and send notifications
+async fn poll_and_notify(
+    client: Arc<LambdaClient>,
+    notifier: Notifier,
+    instance_id: String,
+    instance_name: Option<String>,
+    gpu_type: String,
+    region: String,
+    max_wait: Duration,
+) {
+    let launched_at = std::time::Instant::now();
+    let instance = match wait_for_ip(&client, &instance_id, max_wait).await {
+        Ok(instance) => instance,
+        Err(e) => {
+            eprintln!("[notify] Stopping notifications: {}", e);
+            return;
+        }
+    };
+    let ready_after = launched_at.elapsed();
+    eprintln!(
+        "[notify] Instance {} got an IP {} after launch",
+        instance_id,
+        format_elapsed(ready_after)
+    );
+
+    let msg = InstanceReadyMessage {
+        instance_id: instance_id.clone(),
+        instance_name,
+        ip: instance.ip.unwrap_or_default(),
+        gpu_type,
+        region,
+        region_description: instance.region.and_then(|r| r.description),
+        filesystems: instance.file_system_names.unwrap_or_default(),
+        ready_after: Some(ready_after),
+    };
+    if !log_notify_results(&instance_id, notifier.send_all(&msg).await) {
+        eprintln!(
+            "[notify] Instance {} is ready but no channel was notified; connect with: {}",
+            instance_id,
+            msg.ssh_command()
+        );
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Parse command line arguments
@@ -450,7 +1121,6 @@ async fn main() -> Result<()> {
         Ok(s) => s,
         Err(e) => {
             eprintln!("Failed to initialize Lambda service: {}", e);
-            eprintln!("Make sure LAMBDA_API_KEY environment variable is set.");
             std::process::exit(1);
         }
     };
@@ -463,3 +1133,37 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_error() {
+        let err = tool_error(LambdaError::ApiKeyNotSet.into());
+        assert!(err.message.starts_with("No API key is configured."));
+        assert_eq!(
+            err.data,
+            Some(serde_json::json!({"kind": "api_key_not_set"}))
+        );
+
+        let err = tool_error(LambdaError::ApiKeyCommandFailed("exit status 1".to_string()).into());
+        assert!(err
+            .message
+            .starts_with("LAMBDA_API_KEY_COMMAND is configured but failed: exit status 1."));
+        assert_eq!(
+            err.data,
+            Some(serde_json::json!({"kind": "api_key_command_failed"}))
+        );
+
+        let err =
+            tool_error(anyhow::Error::from(LambdaError::Unauthorized).context("Listing failed"));
+        // Other errors keep their message, and the kind comes from under any context
+        assert_eq!(err.message, "Listing failed");
+        assert_eq!(err.data, Some(serde_json::json!({"kind": "unauthorized"})));
+
+        let err = tool_error(anyhow::anyhow!("Something else"));
+        assert_eq!(err.message, "Something else");
+        assert_eq!(err.data, None);
+    }
+}
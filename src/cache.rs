@@ -0,0 +1,77 @@
+//! Short-lived cache for API reads, so bursts of identical requests share one response.
+//!
+//! The MCP server keeps one per read-heavy listing and invalidates it whenever
+//! a tool changes what the listing would return.
+
+use anyhow::Result;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A single cached value that expires after `ttl`
+#[derive(Debug)]
+pub struct TtlCache<T> {
+    ttl: Duration,
+    entry: Mutex<Option<(Instant, T)>>,
+}
+
+impl<T: Clone> TtlCache<T> {
+    /// A zero `ttl` disables caching
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: Mutex::new(None),
+        }
+    }
+
+    /// The cached value, if it hasn't expired
+    pub fn get(&self) -> Option<T> {
+        let entry = self.entry.lock().ok()?;
+        entry
+            .as_ref()
+            .filter(|(stored, _)| stored.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn set(&self, value: T) {
+        if let Ok(mut entry) = self.entry.lock() {
+            *entry = Some((Instant::now(), value));
+        }
+    }
+
+    /// Drop the cached value so the next read fetches a fresh one
+    pub fn invalidate(&self) {
+        if let Ok(mut entry) = self.entry.lock() {
+            *entry = None;
+        }
+    }
+
+    /// The cached value, or the result of `fetch` (cached if it succeeds)
+    pub async fn get_or_fetch(&self, fetch: impl Future<Output = Result<T>>) -> Result<T> {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+        let value = fetch.await?;
+        self.set(value.clone());
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ttl_cache() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get(), None);
+        cache.set(1);
+        assert_eq!(cache.get(), Some(1));
+        cache.invalidate();
+        assert_eq!(cache.get(), None);
+
+        let disabled = TtlCache::new(Duration::ZERO);
+        disabled.set(1);
+        assert_eq!(disabled.get(), None);
+    }
+}
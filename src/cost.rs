@@ -0,0 +1,117 @@
+//! Cost estimation for running instances.
+//!
+//! Prices come from the instance-types endpoint, fetched once per estimate (see
+//! `LambdaClient::instance_types_by_name`); an instance whose type has no known
+//! price is listed but contributes nothing to the totals.
+
+use crate::api::{Instance, InstanceTypeData};
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub const HOURS_PER_DAY: f64 = 24.0;
+/// Average number of hours in a month (365 * 24 / 12)
+pub const HOURS_PER_MONTH: f64 = 730.0;
+
+/// Estimated spend for a single instance (in dollars)
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceCost {
+    pub instance_id: String,
+    pub name: Option<String>,
+    pub instance_type: String,
+    /// Raw price from the API, if the instance type is known
+    pub price_cents_per_hour: Option<i32>,
+    pub hourly: f64,
+    pub daily: f64,
+    pub monthly: f64,
+}
+
+/// Estimated spend across all running instances (in dollars)
+#[derive(Debug, Clone, Serialize)]
+pub struct FleetCost {
+    pub instances: Vec<InstanceCost>,
+    pub hourly: f64,
+    pub daily: f64,
+    pub monthly: f64,
+}
+
+impl InstanceCost {
+    /// Estimate one instance's spend, looking its price up in `types` (keyed by name)
+    pub fn new(instance: &Instance, types: &HashMap<String, InstanceTypeData>) -> Self {
+        let instance_type = instance.type_name().unwrap_or("unknown").to_string();
+        let price_cents_per_hour = types.get(&instance_type).map(|t| t.price_cents_per_hour);
+        let hourly = price_cents_per_hour.unwrap_or(0) as f64 / 100.0;
+
+        Self {
+            instance_id: instance.id.clone().unwrap_or_else(|| "N/A".to_string()),
+            name: instance.name.clone(),
+            instance_type,
+            price_cents_per_hour,
+            hourly,
+            daily: hourly * HOURS_PER_DAY,
+            monthly: hourly * HOURS_PER_MONTH,
+        }
+    }
+}
+
+/// Estimate the current spend of `instances` using prices from `types` (keyed by name)
+pub fn estimate_fleet_cost(
+    instances: &[Instance],
+    types: &HashMap<String, InstanceTypeData>,
+) -> FleetCost {
+    let instances: Vec<InstanceCost> = instances
+        .iter()
+        .map(|inst| InstanceCost::new(inst, types))
+        .collect();
+
+    let hourly: f64 = instances.iter().map(|c| c.hourly).sum();
+
+    FleetCost {
+        instances,
+        hourly,
+        daily: hourly * HOURS_PER_DAY,
+        monthly: hourly * HOURS_PER_MONTH,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(id: &str, gpu: &str) -> Instance {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "instance_type": { "name": gpu }
+        }))
+        .unwrap()
+    }
+
+    fn instance_type(name: &str, price_cents_per_hour: i32) -> InstanceTypeData {
+        InstanceTypeData {
+            name: name.to_string(),
+            description: String::new(),
+            price_cents_per_hour,
+            vcpus: 0,
+            memory_gib: 0,
+            storage_gib: 0,
+            regions_available: vec![],
+        }
+    }
+
+    #[test]
+    fn test_estimate_fleet_cost() {
+        let instances = vec![
+            instance("a", "gpu_1x_a10"),
+            instance("b", "gpu_1x_a10"),
+            instance("c", "gpu_1x_mystery"),
+        ];
+        let types = HashMap::from([("gpu_1x_a10".to_string(), instance_type("gpu_1x_a10", 75))]);
+
+        let cost = estimate_fleet_cost(&instances, &types);
+        assert_eq!(cost.instances.len(), 3);
+        assert_eq!(cost.instances[0].price_cents_per_hour, Some(75));
+        assert_eq!(cost.instances[2].price_cents_per_hour, None);
+        assert!((cost.hourly - 1.5).abs() < 1e-9);
+        assert!((cost.daily - 36.0).abs() < 1e-9);
+        assert!((cost.monthly - 1095.0).abs() < 1e-9);
+    }
+}
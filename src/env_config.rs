@@ -0,0 +1,134 @@
+//! `LAMBDA_CONFIG_JSON`: every MCP server setting in one env var.
+//!
+//! The value is a JSON object whose keys are the `LAMBDA_*` variable names,
+//! lowercased and without the prefix:
+//!
+//! ```text
+//! LAMBDA_CONFIG_JSON='{"api_key_command": "op read op://vault/lambda/key",
+//!                      "notify_slack_webhook": "https://hooks.slack.com/...",
+//!                      "notify_max_wait": 1800}'
+//! ```
+//!
+//! Each key fills in its variable only when that variable isn't already set,
+//! so individual env vars override the blob.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+
+/// Keys the blob may contain, each standing for `LAMBDA_<KEY>`
+pub const KEYS: [&str; 18] = [
+    "api_key",
+    "api_key_command",
+    "api_key_ttl",
+    "extra_headers",
+    "proxy_auth_command",
+    "proxy_auth_header",
+    "proxy_auth_ttl",
+    "retry_budget",
+    "gpu_aliases",
+    "require_name",
+    "mcp_cache_ttl",
+    "notify_slack_webhook",
+    "notify_discord_webhook",
+    "notify_telegram_bot_token",
+    "notify_telegram_chat_id",
+    "notify_plain",
+    "notify_verbosity",
+    "notify_max_wait",
+];
+
+/// Parse a config blob into `(LAMBDA_<KEY>, value)` pairs
+pub fn parse(json: &str) -> Result<Vec<(String, String)>> {
+    let value: Value = serde_json::from_str(json).context("Invalid LAMBDA_CONFIG_JSON")?;
+    let Value::Object(entries) = value else {
+        return Err(anyhow!("LAMBDA_CONFIG_JSON must be a JSON object"));
+    };
+
+    entries
+        .into_iter()
+        .map(|(key, value)| {
+            if !KEYS.contains(&key.as_str()) {
+                return Err(anyhow!(
+                    "Unknown key '{}' in LAMBDA_CONFIG_JSON. Available: {}",
+                    key,
+                    KEYS.join(", ")
+                ));
+            }
+            let value = match value {
+                Value::String(s) => s,
+                Value::Number(n) => n.to_string(),
+                Value::Bool(b) => b.to_string(),
+                _ => {
+                    return Err(anyhow!(
+                        "LAMBDA_CONFIG_JSON key '{}' must be a string, number, or boolean",
+                        key
+                    ))
+                }
+            };
+            Ok((format!("LAMBDA_{}", key.to_uppercase()), value))
+        })
+        .collect()
+}
+
+/// Set the variables from `LAMBDA_CONFIG_JSON` that aren't already set.
+///
+/// An empty variable counts as unset, as it does everywhere settings are read.
+/// Call this before anything reads its settings from the environment.
+pub fn apply() -> Result<()> {
+    let Ok(json) = std::env::var("LAMBDA_CONFIG_JSON") else {
+        return Ok(());
+    };
+    for (name, value) in unset_only(parse(&json)?, |name| std::env::var(name).ok()) {
+        std::env::set_var(name, value);
+    }
+    Ok(())
+}
+
+/// The `pairs` whose variable `current` reports unset or empty
+fn unset_only(
+    pairs: Vec<(String, String)>,
+    current: impl Fn(&str) -> Option<String>,
+) -> Vec<(String, String)> {
+    pairs
+        .into_iter()
+        .filter(|(name, _)| current(name).filter(|v| !v.is_empty()).is_none())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_json() {
+        let pairs =
+            parse(r#"{"api_key": "secret", "notify_max_wait": 1800, "notify_plain": true}"#)
+                .unwrap();
+        assert_eq!(
+            pairs,
+            vec![
+                ("LAMBDA_API_KEY".to_string(), "secret".to_string()),
+                ("LAMBDA_NOTIFY_MAX_WAIT".to_string(), "1800".to_string()),
+                ("LAMBDA_NOTIFY_PLAIN".to_string(), "true".to_string()),
+            ]
+        );
+
+        let err = parse(r#"{"apikey": "secret"}"#).unwrap_err().to_string();
+        assert!(err.starts_with("Unknown key 'apikey'"));
+        assert!(parse(r#"{"notify_max_wait": [1800]}"#).is_err());
+        assert!(parse(r#"["api_key"]"#).is_err());
+        assert!(parse("{").is_err());
+
+        // An empty variable (e.g. from a config template) doesn't hide the blob's value
+        let pairs = parse(r#"{"api_key": "secret", "notify_plain": true}"#).unwrap();
+        let current = |name: &str| match name {
+            "LAMBDA_API_KEY" => Some(String::new()),
+            "LAMBDA_NOTIFY_PLAIN" => Some("false".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            unset_only(pairs, current),
+            vec![("LAMBDA_API_KEY".to_string(), "secret".to_string())]
+        );
+    }
+}
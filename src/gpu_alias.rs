@@ -0,0 +1,64 @@
+//! Short names for GPU instance types.
+//!
+//! `LAMBDA_GPU_ALIASES=a100=gpu_1x_a100,8xh100=gpu_8x_h100` lets `--gpu a100`
+//! stand in for `gpu_1x_a100`. Names that aren't aliases are used as given.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// Alias → instance type name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GpuAliases {
+    aliases: HashMap<String, String>,
+}
+
+impl GpuAliases {
+    /// Parse `alias=instance_type` pairs separated by commas
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut aliases = HashMap::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (alias, gpu) = entry
+                .split_once('=')
+                .map(|(alias, gpu)| (alias.trim(), gpu.trim()))
+                .filter(|(alias, gpu)| !alias.is_empty() && !gpu.is_empty())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Invalid GPU alias '{}' (expected alias=instance_type)",
+                        entry
+                    )
+                })?;
+            aliases.insert(alias.to_string(), gpu.to_string());
+        }
+        Ok(Self { aliases })
+    }
+
+    /// Aliases from `LAMBDA_GPU_ALIASES`, or none if unset
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("LAMBDA_GPU_ALIASES") {
+            Ok(spec) => Self::parse(&spec),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// The instance type `gpu` is an alias for, or `gpu` itself
+    pub fn resolve<'a>(&'a self, gpu: &'a str) -> &'a str {
+        self.aliases.get(gpu).map(String::as_str).unwrap_or(gpu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpu_aliases() {
+        let aliases = GpuAliases::parse(" a100 = gpu_1x_a100 ,8xh100=gpu_8x_h100,").unwrap();
+        assert_eq!(aliases.resolve("a100"), "gpu_1x_a100");
+        assert_eq!(aliases.resolve("8xh100"), "gpu_8x_h100");
+        assert_eq!(aliases.resolve("gpu_1x_a10"), "gpu_1x_a10");
+
+        assert_eq!(GpuAliases::parse("").unwrap(), GpuAliases::default());
+        assert!(GpuAliases::parse("a100").is_err());
+        assert!(GpuAliases::parse("a100=").is_err());
+    }
+}
@@ -1,5 +1,24 @@
 pub mod api;
+pub mod cache;
+pub mod cost;
+pub mod env_config;
+pub mod gpu_alias;
+pub mod metrics;
 pub mod notify;
+pub mod price_history;
+pub mod retry;
+pub mod settings;
+pub mod spec;
+pub mod template;
 
-pub use api::{Filesystem, Instance, InstanceTypeData, LambdaClient, LambdaError, LaunchResult};
-pub use notify::{InstanceReadyMessage, Notifier, NotifyConfig};
+pub use api::{
+    Filesystem, Instance, InstanceStatus, InstanceTypeData, LambdaClient, LambdaError,
+    LaunchOptions, LaunchResult,
+};
+pub use cost::{FleetCost, InstanceCost};
+pub use gpu_alias::GpuAliases;
+pub use notify::{
+    BatchReadyMessage, BatchTracker, ChannelStatus, InstanceReadyMessage, Notifier, NotifyConfig,
+};
+pub use retry::RetryBudget;
+pub use spec::{FleetSpec, InstanceSpec};
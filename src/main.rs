@@ -1,41 +1,218 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::Local;
-use clap::{Parser, Subcommand};
-use colored::Colorize;
+use clap::{Parser, Subcommand, ValueEnum};
+use colored::{ColoredString, Colorize};
 use crossterm::{
     cursor::MoveTo,
     execute,
     terminal::{Clear, ClearType},
 };
-use lambda_cli::api::{LambdaClient, LambdaError};
-use lambda_cli::notify::{InstanceReadyMessage, Notifier, NotifyConfig};
-use prettytable::{row, Table};
-use std::io::{stdout, Write};
+use lambda_cli::api::{
+    format_elapsed, normalize_region, Filesystem, Instance, InstanceStatus, InstanceTypeData,
+    LambdaClient, LambdaError, LaunchOptions, API_BASE_URL, DEFAULT_TIMEOUT_SECS,
+    POLL_INTERVAL_FLOOR,
+};
+use lambda_cli::cost::{HOURS_PER_DAY, HOURS_PER_MONTH};
+use lambda_cli::gpu_alias::GpuAliases;
+use lambda_cli::metrics;
+use lambda_cli::notify::{
+    self, BatchTracker, CapacityChangeMessage, ChannelStatus, InstanceReadyMessage, Notifier,
+    NotifyConfig,
+};
+use lambda_cli::price_history;
+use lambda_cli::settings::{self, Setting};
+use lambda_cli::spec::{FleetSpec, ImportProgress};
+use lambda_cli::template::Template;
+use prettytable::format::{FormatBuilder, LinePosition, LineSeparator, TableFormat};
+use prettytable::{row, Cell, Row, Table};
+use serde::Serialize;
+use std::io::{stdout, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 
 /// A command-line tool for Lambda cloud GPU API
 #[derive(Parser)]
 #[command(name = "lambda")]
-#[command(version = "0.2.0")]
+#[command(version)]
 #[command(about = "A command-line tool for Lambda cloud GPU API", long_about = None)]
 struct Cli {
+    /// When to use colored output (NO_COLOR is respected in auto mode)
+    #[arg(long, value_enum, global = true, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// Disable colored output (same as --color never)
+    #[arg(long, global = true, conflicts_with = "color")]
+    no_color: bool,
+    /// Border style for table output
+    #[arg(long, value_enum, global = true, default_value_t = TableStyle::Ascii)]
+    table_style: TableStyle,
+    /// Read the API key from stdin (first line) instead of LAMBDA_API_KEY
+    #[arg(long, global = true)]
+    api_key_stdin: bool,
+    /// Don't load a .env file; use only the real environment (also LAMBDA_NO_DOTENV=1)
+    #[arg(long, global = true)]
+    no_dotenv: bool,
+    /// Print pretty-printed JSON instead of a table (list, running, filesystems)
+    #[arg(long, global = true)]
+    json: bool,
+    /// Print single-line JSON instead of a table (list, running, filesystems)
+    #[arg(long, global = true, conflicts_with = "json")]
+    json_compact: bool,
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+/// How command results are printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Table,
+    Json,
+    JsonCompact,
+}
+
+/// Output settings shared by all commands
+struct Output {
+    format: OutputFormat,
+}
+
+impl Output {
+    fn from_cli(cli: &Cli) -> Self {
+        let format = if cli.json {
+            OutputFormat::Json
+        } else if cli.json_compact {
+            OutputFormat::JsonCompact
+        } else {
+            OutputFormat::Table
+        };
+        Self { format }
+    }
+
+    fn is_json(&self) -> bool {
+        self.format != OutputFormat::Table
+    }
+
+    fn to_json<T: Serialize>(&self, value: &T) -> Result<String> {
+        Ok(match self.format {
+            OutputFormat::JsonCompact => serde_json::to_string(value)?,
+            _ => serde_json::to_string_pretty(value)?,
+        })
+    }
+
+    fn print_json<T: Serialize>(&self, value: &T) -> Result<()> {
+        println!("{}", self.to_json(value)?);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum TableStyle {
+    Ascii,
+    Markdown,
+    Borderless,
+    Unicode,
+}
+
+impl TableStyle {
+    fn format(self) -> TableFormat {
+        use prettytable::format::consts;
+        match self {
+            TableStyle::Ascii => *consts::FORMAT_NO_TITLE,
+            TableStyle::Markdown => FormatBuilder::new()
+                .column_separator('|')
+                .borders('|')
+                .separator(LinePosition::Title, LineSeparator::new('-', '|', '|', '|'))
+                .padding(1, 1)
+                .build(),
+            TableStyle::Borderless => *consts::FORMAT_CLEAN,
+            TableStyle::Unicode => *consts::FORMAT_BOX_CHARS,
+        }
+    }
+}
+
+/// Style for every table, set once from `--table-style`
+static TABLE_STYLE: OnceLock<TableStyle> = OnceLock::new();
+
+/// Set by `find --regions-json`, whose stdout is reserved for snapshot lines
+static PROGRESS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// `println!` for progress messages, which move to stderr while stdout carries JSON lines
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if PROGRESS_TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// `print!` and flush, for a progress line redrawn in place with `\r`
+macro_rules! progress_inline {
+    ($($arg:tt)*) => {
+        if PROGRESS_TO_STDERR.load(Ordering::Relaxed) {
+            eprint!($($arg)*)
+        } else {
+            print!($($arg)*);
+            stdout().flush().ok();
+        }
+    };
+}
+
+/// Create a table with the given header row, in the selected `--table-style`
+fn new_table(header: Row) -> Table {
+    let mut table = Table::new();
+    table.set_format(
+        TABLE_STYLE
+            .get()
+            .copied()
+            .unwrap_or(TableStyle::Ascii)
+            .format(),
+    );
+    table.set_titles(header);
+    table
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// List all available GPU instances
-    List,
+    List {
+        /// Unit to show prices in
+        #[arg(long, value_enum, default_value_t = PriceUnit::Hour)]
+        price_unit: PriceUnit,
+        /// Show prices as whole cents instead of dollars (JSON always has price_cents_per_hour)
+        #[arg(long)]
+        price_cents: bool,
+        /// Table columns to show, in order (default: all)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        columns: Vec<ListColumn>,
+    },
     /// Start a GPU instance with the specified SSH key
     Start {
+        /// GPU instance type, instead of --gpu (e.g. `lambda start gpu_1x_a100 my-key`)
+        #[arg(value_name = "GPU")]
+        gpu_arg: Option<String>,
+        /// SSH key name, instead of --ssh
+        #[arg(value_name = "SSH_KEY")]
+        ssh_arg: Option<String>,
         /// GPU instance type (e.g., gpu_1x_a100)
-        #[arg(short, long)]
-        gpu: String,
+        #[arg(short, long, required_unless_present = "gpu_arg")]
+        gpu: Option<String>,
         /// SSH key name to use for the instance
-        #[arg(short, long)]
-        ssh: String,
+        #[arg(
+            short,
+            long,
+            required_unless_present = "ssh_arg",
+            required_unless_present_all = ["gpu", "gpu_arg"]
+        )]
+        ssh: Option<String>,
         /// Optional name for the instance
         #[arg(short, long)]
         name: Option<String>,
@@ -45,41 +222,178 @@ enum Commands {
         /// Filesystem name to attach (must be in same region)
         #[arg(short, long)]
         filesystem: Option<String>,
+        /// Skip the local capacity check for --region and let the API accept or reject it
+        #[arg(long, requires = "region")]
+        force_region: bool,
+        /// Refuse to launch without --name (also LAMBDA_REQUIRE_NAME=1)
+        #[arg(long)]
+        name_required: bool,
+        /// Fail instead of warning if a running instance already has --name
+        #[arg(long, requires = "name")]
+        strict_names: bool,
+        /// Ask before launching a type that costs more than this many $/hr (default 5)
+        #[arg(long, value_name = "DOLLARS", num_args = 0..=1, default_missing_value = "5")]
+        confirm_price: Option<f64>,
+        /// Skip the --confirm-price prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
+        /// GPU type to try if the ones before it have no capacity (repeat for more, in order)
+        #[arg(long, value_name = "GPU")]
+        fallback_gpu: Vec<String>,
+        /// Seconds to wait for the launch request to be accepted (default: 30, like other requests)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        launch_timeout: Option<u64>,
         /// Disable notifications even if LAMBDA_NOTIFY_* env vars are set
         #[arg(long)]
         no_notify: bool,
+        /// Seconds between status checks while waiting for the instance (minimum 2)
+        #[arg(long, default_value_t = DEFAULT_POLL_INTERVAL_SECS, value_parser = poll_interval_parser())]
+        poll_interval: u64,
+        /// Open the instance's Jupyter URL in a browser once it is ready
+        #[arg(long)]
+        open: bool,
     },
     /// Stop a specified GPU instance
     Stop {
-        /// Instance ID to terminate
+        /// Instance ID to terminate (comma-separate to terminate several in one request;
+        /// prompts with a list if omitted in a terminal)
+        #[arg(short = 'i', long = "instance-id", value_delimiter = ',')]
+        instance_ids: Vec<String>,
+        /// Show which instance would be terminated without terminating it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print the raw API response for an instance (for debugging)
+    Describe {
+        /// Instance ID to describe (prompts with a list if omitted in a terminal)
         #[arg(short = 'i', long)]
-        instance_id: String,
+        instance_id: Option<String>,
     },
     /// List all running GPU instances
-    Running,
+    Running {
+        /// Only show instances of this GPU type
+        #[arg(short, long)]
+        gpu: Option<String>,
+        /// Only show instances launched with this SSH key
+        #[arg(long)]
+        ssh_key: Option<String>,
+        /// Order instances by this field (cost sorts most expensive first)
+        #[arg(long, value_enum)]
+        sort: Option<RunningSort>,
+        /// Show at most this many instances (after sorting)
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Print each instance as this template, e.g. "{id} {ip} {status}"
+        /// (fields: id, name, ip, status, type, region, ssh_keys)
+        #[arg(long, conflicts_with = "count_only")]
+        template: Option<String>,
+        /// Print only the number of matching instances
+        #[arg(long)]
+        count_only: bool,
+        /// Table columns to show, in order (default: all)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        columns: Vec<RunningColumn>,
+        /// Refresh every SECS seconds until interrupted (JSON output becomes one snapshot per line)
+        #[arg(long, value_name = "SECS", value_parser = poll_interval_parser())]
+        watch: Option<u64>,
+    },
+    /// Wait for an instance to become active
+    Wait {
+        /// Instance ID to wait for (repeat or comma-separate to wait on several;
+        /// prompts with a list if omitted in a terminal)
+        #[arg(short = 'i', long = "instance-id", value_delimiter = ',')]
+        instance_ids: Vec<String>,
+        /// Maximum time to wait in seconds
+        #[arg(long, default_value_t = 300)]
+        timeout: u64,
+        /// Require the instance to stay active for this many seconds before reporting ready
+        #[arg(long, value_name = "SECS")]
+        require_stable: Option<u64>,
+        /// Seconds between status checks (minimum 2)
+        #[arg(long, default_value_t = DEFAULT_POLL_INTERVAL_SECS, value_parser = poll_interval_parser())]
+        poll_interval: u64,
+        /// Count --timeout from this launch time (RFC 3339 or Unix seconds), so a restarted
+        /// wait keeps the original deadline
+        #[arg(long, value_name = "TIME", value_parser = parse_launch_time)]
+        resume_from: Option<chrono::DateTime<chrono::Utc>>,
+    },
     /// Continuously find and start a GPU instance when it becomes available
     Find {
+        /// GPU instance type, instead of --gpu (e.g. `lambda find gpu_8x_h100 my-key`)
+        #[arg(value_name = "GPU")]
+        gpu_arg: Option<String>,
+        /// SSH key name, instead of --ssh
+        #[arg(value_name = "SSH_KEY")]
+        ssh_arg: Option<String>,
         /// GPU instance type to find
-        #[arg(short, long)]
-        gpu: String,
+        #[arg(short, long, required_unless_present = "gpu_arg")]
+        gpu: Option<String>,
         /// SSH key name to use when launching
-        #[arg(short, long)]
-        ssh: String,
-        /// Polling interval in seconds
+        #[arg(
+            short,
+            long,
+            required_unless_present = "ssh_arg",
+            required_unless_present_all = ["gpu", "gpu_arg"]
+        )]
+        ssh: Option<String>,
+        /// Polling interval in seconds (minimum 5 unless --allow-fast-polling)
         #[arg(long, default_value_t = 10)]
         interval: u64,
+        /// Allow --interval below the minimum (uses more of your rate limit)
+        #[arg(long)]
+        allow_fast_polling: bool,
         /// Optional name for the instance when launched
         #[arg(short, long)]
         name: Option<String>,
+        /// Append the launch time to --name (e.g. dev-20250101-120000) so repeated finds get unique names
+        #[arg(long, requires = "name")]
+        timestamp_name: bool,
+        /// Refuse to launch without --name (also LAMBDA_REQUIRE_NAME=1)
+        #[arg(long)]
+        name_required: bool,
+        /// Periodically log a heartbeat with the last successful poll and error count
+        #[arg(long)]
+        keep_alive: bool,
+        /// Only launch in these regions (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        only_regions: Vec<String>,
+        /// Never launch in these regions (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        exclude_regions: Vec<String>,
+        /// After this many checks without capacity in the allowed regions, launch in any region
+        #[arg(long, value_name = "CHECKS", conflicts_with = "filesystem")]
+        fallback_any_region: Option<u32>,
         /// Filesystem name to attach when launched (must be in same region)
         #[arg(short, long)]
         filesystem: Option<String>,
         /// Disable notifications even if LAMBDA_NOTIFY_* env vars are set
         #[arg(long)]
         no_notify: bool,
+        /// Don't check API access before polling starts
+        #[arg(long)]
+        skip_preflight: bool,
+        /// Print each check's per-region availability as a JSON line; all other output goes to stderr
+        #[arg(long)]
+        regions_json: bool,
+        /// Also write each check's availability snapshot to this file (replaced atomically)
+        #[arg(long, value_name = "PATH")]
+        status_file: Option<std::path::PathBuf>,
+        /// Notify whenever the set of regions with capacity changes, not only on launch
+        #[arg(long, conflicts_with = "no_notify")]
+        notify_on_change: bool,
     },
     /// List all filesystems (persistent storage)
-    Filesystems,
+    Filesystems {
+        /// Only show filesystems in this region (e.g. the region you're launching in)
+        #[arg(short, long)]
+        region: Option<String>,
+    },
+    /// Show current usage of a filesystem and which instances use it
+    FilesystemStatus {
+        /// Filesystem ID to inspect
+        #[arg(short = 'i', long)]
+        filesystem_id: String,
+    },
     /// Create a new filesystem
     CreateFilesystem {
         /// Name for the filesystem
@@ -94,67 +408,674 @@ enum Commands {
         /// Filesystem ID to delete
         #[arg(short = 'i', long)]
         filesystem_id: String,
+        /// Skip the confirmation prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Launch an instance, run a command on it over SSH, and optionally terminate it
+    Run {
+        /// GPU instance type (e.g., gpu_1x_a100)
+        #[arg(short, long)]
+        gpu: String,
+        /// SSH key name to launch with
+        #[arg(short, long)]
+        ssh: String,
+        /// Command to run on the instance
+        #[arg(short, long)]
+        command: String,
+        /// Optional name for the instance
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Region to launch in (auto-selects first available if not specified)
+        #[arg(short, long)]
+        region: Option<String>,
+        /// Filesystem name to attach (must be in same region)
+        #[arg(short, long)]
+        filesystem: Option<String>,
+        /// Private key file for ssh (default: ssh's own identity search)
+        #[arg(long)]
+        identity: Option<std::path::PathBuf>,
+        /// Terminate the instance when the command finishes, even if it fails
+        #[arg(long)]
+        terminate_on_exit: bool,
+    },
+    /// Launch, check, and terminate an instance to verify the account end to end
+    Selftest {
+        /// GPU instance type to test with (default: cheapest available)
+        #[arg(short, long)]
+        gpu: Option<String>,
+        /// SSH key name to launch with
+        #[arg(short, long)]
+        ssh: String,
+        /// Skip the confirmation prompt (the test instance is billed while it runs)
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Save running instances' launch settings to a spec file for `import-instances`
+    ExportInstances {
+        /// File to write (default: stdout)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Launch every instance described in a spec file from `export-instances`
+    ImportInstances {
+        /// Spec file to read
+        #[arg(short, long)]
+        file: std::path::PathBuf,
+        /// Continue an interrupted import, launching only what it didn't
+        #[arg(long)]
+        resume: bool,
+        /// Wait for the launched instances and send one notification for the whole batch
+        #[arg(long)]
+        notify: bool,
+        /// Don't check API access before launching
+        #[arg(long)]
+        skip_preflight: bool,
+        /// Skip the confirmation prompt (every instance is billed)
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Show prices recorded by `list` for a GPU type (requires LAMBDA_PRICE_HISTORY)
+    PriceHistory {
+        /// GPU instance type to show
+        #[arg(short, long)]
+        gpu: String,
+    },
+    /// Exit 0 if a GPU type has capacity, 3 if not (for scripts; prints nothing without --json)
+    Available {
+        /// GPU instance type to check
+        #[arg(short, long)]
+        gpu: String,
+        /// Only count capacity in this region
+        #[arg(short, long)]
+        region: Option<String>,
     },
+    /// Serve fleet gauges for Prometheus at http://BIND:PORT/metrics until interrupted
+    ServeMetrics {
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 9464)]
+        port: u16,
+        /// Address to listen on (use 0.0.0.0 to accept scrapes from other hosts)
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+    },
+    /// Send a test notification to all configured channels
+    NotifyTest,
+    /// Show which notification channels are configured, without sending anything
+    NotifyStatus,
+    /// Show the settings in effect and where each comes from (secrets redacted)
+    Config,
+    /// Show the CLI version and check API key and API connectivity
+    Version,
+}
+
+/// Default seconds between instance status checks while waiting
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 10;
+/// Lowest accepted `--poll-interval`, to avoid hammering the API
+const MIN_POLL_INTERVAL_SECS: u64 = 2;
+
+fn poll_interval_parser() -> clap::builder::RangedU64ValueParser<u64> {
+    clap::value_parser!(u64).range(MIN_POLL_INTERVAL_SECS..)
+}
+
+/// Parse `wait --resume-from`: RFC 3339 (`2025-01-01T12:00:00Z`) or Unix seconds
+fn parse_launch_time(value: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    if let Ok(secs) = value.parse::<i64>() {
+        return chrono::DateTime::from_timestamp(secs, 0)
+            .ok_or_else(|| format!("'{}' is out of range", value));
+    }
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|time| time.to_utc())
+        .map_err(|_| {
+            format!(
+                "expected an RFC 3339 time (e.g. 2025-01-01T12:00:00Z) or Unix seconds, got '{}'",
+                value
+            )
+        })
+}
+
+/// Field `lambda running --sort` orders by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum RunningSort {
+    Name,
+    Type,
+    Region,
+    Status,
+    /// Hourly price, most expensive first
+    Cost,
+}
+
+/// Column of the `lambda running` table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum RunningColumn {
+    Id,
+    Name,
+    Type,
+    Region,
+    Status,
+    Ip,
+    #[value(alias = "ssh_keys")]
+    SshKeys,
+}
+
+impl RunningColumn {
+    fn header(self) -> &'static str {
+        match self {
+            Self::Id => "Instance ID",
+            Self::Name => "Name",
+            Self::Type => "Type",
+            Self::Region => "Region",
+            Self::Status => "Status",
+            Self::Ip => "IP Address",
+            Self::SshKeys => "SSH Keys",
+        }
+    }
+
+    fn cell(self, instance: &Instance) -> String {
+        let or_na = |value: Option<&str>| value.unwrap_or("N/A").to_string();
+        match self {
+            Self::Id => or_na(instance.id.as_deref()).cyan().to_string(),
+            Self::Name => instance.name.as_deref().unwrap_or("-").white().to_string(),
+            Self::Type => or_na(instance.type_name()),
+            Self::Region => or_na(instance.region.as_ref().and_then(|r| r.name.as_deref())),
+            Self::Status => {
+                let status = instance.status.as_ref();
+                // The glyph carries the state even when color is disabled
+                let text = format!("{} {}", status_glyph(status), instance.status_display());
+                match status {
+                    Some(InstanceStatus::Active) => text.green().to_string(),
+                    Some(InstanceStatus::Booting) => text.yellow().to_string(),
+                    Some(s) if s.is_failed() => text.red().to_string(),
+                    _ => text,
+                }
+            }
+            Self::Ip => or_na(instance.ip.as_deref()).blue().to_string(),
+            Self::SshKeys => instance
+                .ssh_key_names
+                .as_ref()
+                .map(|keys| keys.join(", "))
+                .unwrap_or_else(|| "N/A".to_string()),
+        }
+    }
+}
+
+/// Column of the `lambda list` table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ListColumn {
+    Type,
+    Description,
+    Price,
+    Vcpus,
+    Memory,
+    Storage,
+    Regions,
+}
+
+/// The chosen columns, or every column when none were given
+fn columns_or_all<T: ValueEnum + Clone>(columns: &[T]) -> Vec<T> {
+    if columns.is_empty() {
+        T::value_variants().to_vec()
+    } else {
+        columns.to_vec()
+    }
+}
+
+/// Time unit used when displaying instance prices
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum PriceUnit {
+    Hour,
+    Day,
+    /// 730 hours (the average month)
+    Month,
+}
+
+impl PriceUnit {
+    fn hours(self) -> f64 {
+        match self {
+            PriceUnit::Hour => 1.0,
+            PriceUnit::Day => HOURS_PER_DAY,
+            PriceUnit::Month => HOURS_PER_MONTH,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            PriceUnit::Hour => "hr",
+            PriceUnit::Day => "day",
+            PriceUnit::Month => "month",
+        }
+    }
 }
 
 fn main() {
-    dotenv::dotenv().ok();
+    let mut cli = Cli::parse();
+    take_positional_args(&mut cli.command);
+    if !cli.no_dotenv && !no_dotenv_env() {
+        dotenv::dotenv().ok();
+    }
 
-    if let Err(e) = run() {
-        eprintln!("{} {}", "Error:".red().bold(), e);
+    let result = GpuAliases::from_env().and_then(|aliases| {
+        resolve_gpu_aliases(&mut cli.command, &aliases);
+        run(&cli)
+    });
+    if let Err(e) = result {
+        let out = Output::from_cli(&cli);
+        if out.is_json() {
+            eprintln!(
+                "{}",
+                out.to_json(&error_json(&e))
+                    .unwrap_or_else(|_| e.to_string())
+            );
+        } else {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+        }
         std::process::exit(1);
     }
 }
 
-fn run() -> Result<()> {
-    let cli = Cli::parse();
+/// Fill `--gpu` and `--ssh` from `start`/`find` positional args.
+///
+/// Two positionals are the GPU and SSH key; a single one fills whichever flag
+/// is missing (the GPU if both are). Flags take precedence. clap has already
+/// checked that this leaves both set.
+fn take_positional_args(command: &mut Option<Commands>) {
+    let (gpu, ssh, gpu_arg, ssh_arg) = match command {
+        Some(
+            Commands::Start {
+                gpu,
+                ssh,
+                gpu_arg,
+                ssh_arg,
+                ..
+            }
+            | Commands::Find {
+                gpu,
+                ssh,
+                gpu_arg,
+                ssh_arg,
+                ..
+            },
+        ) => (gpu, ssh, gpu_arg.take(), ssh_arg.take()),
+        _ => return,
+    };
+    let (gpu_arg, ssh_arg) = match (gpu_arg, ssh_arg) {
+        (Some(arg), None) if gpu.is_some() && ssh.is_none() => (None, Some(arg)),
+        args => args,
+    };
+    if gpu.is_none() {
+        *gpu = gpu_arg;
+    }
+    if ssh.is_none() {
+        *ssh = ssh_arg;
+    }
+}
+
+/// Replace a `--gpu` alias with the instance type it names
+fn resolve_gpu_aliases(command: &mut Option<Commands>, aliases: &GpuAliases) {
+    let gpu = match command {
+        Some(
+            Commands::Run { gpu, .. }
+            | Commands::PriceHistory { gpu }
+            | Commands::Available { gpu, .. },
+        ) => gpu,
+        Some(
+            Commands::Start { gpu: Some(gpu), .. }
+            | Commands::Find { gpu: Some(gpu), .. }
+            | Commands::Running { gpu: Some(gpu), .. }
+            | Commands::Selftest { gpu: Some(gpu), .. },
+        ) => gpu,
+        _ => return,
+    };
+    let resolved = aliases.resolve(gpu).to_string();
+    *gpu = resolved;
+
+    if let Some(Commands::Start { fallback_gpu, .. }) = command {
+        for gpu in fallback_gpu {
+            *gpu = aliases.resolve(gpu).to_string();
+        }
+    }
+}
+
+/// Whether `LAMBDA_NO_DOTENV` asks to skip `.env` (any value other than empty, `0`, or `false`)
+fn no_dotenv_env() -> bool {
+    std::env::var("LAMBDA_NO_DOTENV")
+        .is_ok_and(|v| !matches!(v.trim().to_lowercase().as_str(), "" | "0" | "false"))
+}
+
+/// `{"error": {code, message, kind}}` for `--json` callers.
+///
+/// `kind` is stable (see `LambdaError::kind`); `code` is the HTTP status of the
+/// failed API request, or null.
+fn error_json(e: &anyhow::Error) -> serde_json::Value {
+    let lambda_error = e.chain().find_map(|c| c.downcast_ref::<LambdaError>());
+    let kind = match lambda_error {
+        Some(err) => err.kind(),
+        None if e.chain().any(|c| c.is::<reqwest::Error>()) => "network",
+        None => "other",
+    };
+
+    serde_json::json!({
+        "error": {
+            "code": lambda_error.and_then(LambdaError::status_code),
+            "message": e.to_string(),
+            "kind": kind,
+        }
+    })
+}
+
+/// Apply `--color`/`--no-color` to every colored string printed afterwards
+fn apply_color(cli: &Cli) {
+    let color = if cli.no_color {
+        ColorChoice::Never
+    } else {
+        cli.color
+    };
+    match color {
+        ColorChoice::Always => colored::control::set_override(true),
+        ColorChoice::Never => colored::control::set_override(false),
+        // colored already honors NO_COLOR, CLICOLOR, and non-TTY stdout
+        ColorChoice::Auto => {}
+    }
+}
+
+fn run(cli: &Cli) -> Result<()> {
+    apply_color(cli);
+    TABLE_STYLE.set(cli.table_style).ok();
+    let out = Output::from_cli(cli);
     let rt = Runtime::new()?;
-    let client = LambdaClient::from_env()?;
+
+    // Commands that don't talk to the Lambda API
+    match &cli.command {
+        Some(Commands::NotifyTest) => return notify_test(&rt),
+        Some(Commands::NotifyStatus) => return notify_status(&out),
+        Some(Commands::Config) => return show_config(cli, &out),
+        Some(Commands::Version) => return version(&rt, cli),
+        Some(Commands::PriceHistory { gpu }) => return price_history(&out, gpu),
+        _ => {}
+    }
+
+    let client = create_client(cli)?;
 
     match &cli.command {
-        Some(Commands::List) => list_instances(&rt, &client),
+        Some(Commands::List {
+            price_unit,
+            price_cents,
+            columns,
+        }) => list_instances(&rt, &client, &out, *price_unit, *price_cents, columns),
+        Some(Commands::Available { gpu, region }) => {
+            available(&rt, &client, &out, gpu, region.as_deref())
+        }
+        Some(Commands::ServeMetrics { port, bind }) => serve_metrics(&rt, &client, bind, *port),
         Some(Commands::Start {
             gpu,
             ssh,
+            gpu_arg: _,
+            ssh_arg: _,
             name,
             region,
             filesystem,
+            force_region,
+            name_required,
+            strict_names,
+            confirm_price,
+            yes,
+            fallback_gpu,
+            launch_timeout,
             no_notify,
-        }) => start_instance(
-            &rt,
-            &client,
+            poll_interval,
+            open,
+        }) => {
+            let gpu = gpu.as_deref().expect("clap requires a GPU type");
+            let ssh = ssh.as_deref().expect("clap requires an SSH key");
+            check_name_required(&client, *name_required, name.as_deref())?;
+            if let Some(name) = name {
+                check_name_unused(&rt, &client, name, *strict_names)?;
+            }
+            let launch = LaunchOptions {
+                name: name.clone(),
+                region: region.clone(),
+                filesystem: filesystem.clone(),
+                force_region: *force_region,
+                timeout: launch_timeout.map(Duration::from_secs),
+            };
+            let gpus: Vec<&str> = std::iter::once(gpu)
+                .chain(fallback_gpu.iter().map(String::as_str))
+                .collect();
+            if let (Some(threshold), false) = (confirm_price, yes) {
+                if !confirm_launch_price(&rt, &client, &gpus, *threshold)? {
+                    println!("Aborted.");
+                    return Ok(());
+                }
+            }
+            start_instance(
+                &rt,
+                &client,
+                &gpus,
+                ssh,
+                &launch,
+                *no_notify,
+                *poll_interval,
+                *open,
+            )
+        }
+        Some(Commands::Stop {
+            instance_ids,
+            dry_run,
+        }) => {
+            let instance_ids = if instance_ids.is_empty() {
+                vec![instance_id_or_pick(&rt, &client, None, "stop")?]
+            } else {
+                let ids: Vec<String> = instance_ids
+                    .iter()
+                    .map(|id| id.trim())
+                    .filter(|id| !id.is_empty())
+                    .map(String::from)
+                    .collect();
+                if ids.is_empty() {
+                    anyhow::bail!("--instance-id needs at least one non-empty ID");
+                }
+                ids
+            };
+            stop_instances(&rt, &client, &instance_ids, *dry_run)
+        }
+        Some(Commands::Describe { instance_id }) => {
+            let instance_id =
+                instance_id_or_pick(&rt, &client, instance_id.as_deref(), "describe")?;
+            describe_instance(&rt, &client, &out, &instance_id)
+        }
+        Some(Commands::Running {
             gpu,
-            ssh,
-            name.as_deref(),
-            region.as_deref(),
-            filesystem.as_deref(),
-            *no_notify,
-        ),
-        Some(Commands::Stop { instance_id }) => stop_instance(&rt, &client, instance_id),
-        Some(Commands::Running) => list_running_instances(&rt, &client),
+            ssh_key,
+            sort,
+            limit,
+            template,
+            count_only,
+            columns,
+            watch,
+        }) => {
+            let filter = InstanceFilter {
+                gpu: gpu.as_deref(),
+                ssh_key: ssh_key.as_deref(),
+            };
+            let view = RunningView {
+                sort: *sort,
+                limit: *limit,
+                template: template.as_deref().map(Template::parse).transpose()?,
+                count_only: *count_only,
+                columns: columns.clone(),
+            };
+            match watch {
+                Some(secs) => watch_running_instances(
+                    &rt,
+                    &client,
+                    &out,
+                    &filter,
+                    &view,
+                    Duration::from_secs(*secs),
+                ),
+                None => list_running_instances(&rt, &client, &out, &filter, &view),
+            }
+        }
+        Some(Commands::Wait {
+            instance_ids,
+            timeout,
+            require_stable,
+            poll_interval,
+            resume_from,
+        }) => {
+            let instance_ids = if instance_ids.is_empty() {
+                vec![instance_id_or_pick(&rt, &client, None, "wait for")?]
+            } else {
+                instance_ids.clone()
+            };
+            let timeout = match resume_from {
+                Some(launched) => remaining_timeout(*launched, *timeout)?,
+                None => *timeout,
+            };
+            wait_command(
+                &rt,
+                &client,
+                &instance_ids,
+                timeout,
+                *require_stable,
+                *poll_interval,
+            )
+        }
         Some(Commands::Find {
             gpu,
             ssh,
+            gpu_arg: _,
+            ssh_arg: _,
             interval,
+            allow_fast_polling,
             name,
+            timestamp_name,
+            name_required,
+            keep_alive,
+            only_regions,
+            exclude_regions,
+            fallback_any_region,
             filesystem,
             no_notify,
-        }) => find_and_start_instance(
-            &rt,
-            &client,
-            gpu,
-            ssh,
-            *interval,
-            name.as_deref(),
-            filesystem.as_deref(),
-            *no_notify,
-        ),
-        Some(Commands::Filesystems) => list_filesystems(&rt, &client),
+            skip_preflight,
+            regions_json,
+            status_file,
+            notify_on_change,
+        }) => {
+            let gpu = gpu.as_deref().expect("clap requires a GPU type");
+            let ssh = ssh.as_deref().expect("clap requires an SSH key");
+            // Before polling, so a missing name doesn't surface only once capacity appears
+            check_name_required(&client, *name_required, name.as_deref())?;
+            let change_notifier = if *notify_on_change {
+                let config = NotifyConfig::from_env().ok_or_else(|| {
+                    anyhow!("--notify-on-change needs a notification channel; set the LAMBDA_NOTIFY_* env vars")
+                })?;
+                Some(Notifier::new(config).with_http_client(client.http_client().clone()))
+            } else {
+                None
+            };
+            if !skip_preflight {
+                preflight(&rt, &client)?;
+            }
+            find_and_start_instance(
+                &rt,
+                &client,
+                gpu,
+                ssh,
+                *interval,
+                *allow_fast_polling,
+                name.as_deref(),
+                *timestamp_name,
+                *keep_alive,
+                &RegionFilter {
+                    only: only_regions.iter().map(|r| normalize_region(r)).collect(),
+                    exclude: exclude_regions
+                        .iter()
+                        .map(|r| normalize_region(r))
+                        .collect(),
+                    fallback_after: *fallback_any_region,
+                },
+                filesystem.as_deref(),
+                *no_notify,
+                &FindSnapshots {
+                    stdout: *regions_json,
+                    file: status_file.clone(),
+                },
+                change_notifier.as_ref(),
+            )
+        }
+        Some(Commands::Filesystems { region }) => {
+            list_filesystems(&rt, &client, &out, region.as_deref())
+        }
+        Some(Commands::FilesystemStatus { filesystem_id }) => {
+            filesystem_status(&rt, &client, &out, filesystem_id)
+        }
         Some(Commands::CreateFilesystem { name, region }) => {
             create_filesystem(&rt, &client, name, region)
         }
-        Some(Commands::DeleteFilesystem { filesystem_id }) => {
-            delete_filesystem(&rt, &client, filesystem_id)
+        Some(Commands::DeleteFilesystem { filesystem_id, yes }) => {
+            delete_filesystem(&rt, &client, filesystem_id, *yes)
+        }
+        Some(Commands::Run {
+            gpu,
+            ssh,
+            command,
+            name,
+            region,
+            filesystem,
+            identity,
+            terminate_on_exit,
+        }) => {
+            let launch = LaunchOptions {
+                name: name.clone(),
+                region: region.clone(),
+                filesystem: filesystem.clone(),
+                ..Default::default()
+            };
+            let run = RunOptions {
+                command,
+                identity: identity.as_deref(),
+                terminate_on_exit: *terminate_on_exit,
+            };
+            run_remote(&rt, &client, gpu, ssh, &launch, &run)
+        }
+        Some(Commands::Selftest { gpu, ssh, yes }) => {
+            selftest(&rt, &client, gpu.as_deref(), ssh, *yes)
+        }
+        Some(Commands::ExportInstances { output }) => {
+            export_instances(&rt, &client, output.as_deref())
+        }
+        Some(Commands::ImportInstances {
+            file,
+            resume,
+            notify,
+            skip_preflight,
+            yes,
+        }) => {
+            // Before launching, so a missing channel isn't found only once instances are billed
+            let notifier = if *notify {
+                let config = NotifyConfig::from_env().ok_or_else(|| {
+                    anyhow!(
+                        "--notify needs a notification channel; set the LAMBDA_NOTIFY_* env vars"
+                    )
+                })?;
+                Some(Notifier::new(config).with_http_client(client.http_client().clone()))
+            } else {
+                None
+            };
+            if !skip_preflight {
+                preflight(&rt, &client)?;
+            }
+            import_instances(&rt, &client, file, *resume, notifier.as_ref(), *yes)
+        }
+        Some(
+            Commands::NotifyTest
+            | Commands::NotifyStatus
+            | Commands::Config
+            | Commands::Version
+            | Commands::PriceHistory { .. },
+        ) => {
+            unreachable!("handled before client creation")
         }
         None => validate_api_key(&rt, &client),
     }
@@ -166,236 +1087,1790 @@ fn validate_api_key(rt: &Runtime, client: &LambdaClient) -> Result<()> {
     Ok(())
 }
 
-fn list_instances(rt: &Runtime, client: &LambdaClient) -> Result<()> {
-    let types = rt.block_on(client.list_instance_types())?;
+/// Fail fast on a bad key or unreachable API before a long-running command starts
+fn preflight(rt: &Runtime, client: &LambdaClient) -> Result<()> {
+    rt.block_on(client.validate_api_key()).map_err(|e| {
+        let message = format!(
+            "Pre-flight check failed: {} (pass --skip-preflight to skip it)",
+            e
+        );
+        e.context(message)
+    })
+}
 
-    let mut table = Table::new();
-    table.add_row(row![
-        "Instance Type",
-        "Description",
-        "Price ($/hr)",
-        "vCPUs",
-        "Memory (GiB)",
-        "Storage (GiB)",
-        "Available Regions"
-    ]);
+fn create_client(cli: &Cli) -> Result<LambdaClient> {
+    if cli.api_key_stdin {
+        LambdaClient::from_stdin()
+    } else {
+        LambdaClient::from_env()
+    }
+}
 
-    for t in types {
-        let availability = if t.regions_available.is_empty() {
-            "None".red().to_string()
-        } else {
-            t.regions_available.join(", ").blue().to_string()
-        };
+fn version(rt: &Runtime, cli: &Cli) -> Result<()> {
+    println!("lambda {}", env!("CARGO_PKG_VERSION"));
+    println!("API base URL: {}", API_BASE_URL);
 
-        let price = format!("${:.2}", t.price_cents_per_hour as f64 / 100.0);
+    let client = match create_client(cli) {
+        Ok(client) => {
+            println!("API key: {}", "resolved".green());
+            client
+        }
+        Err(e) => {
+            println!("API key: {} ({})", "not resolved".red(), e);
+            println!("API: {}", "not checked".dimmed());
+            return Err(anyhow!("Environment check failed"));
+        }
+    };
 
-        table.add_row(row![
-            if t.regions_available.is_empty() {
-                t.name.dimmed().to_string()
-            } else {
-                t.name.green().to_string()
-            },
-            t.description,
-            price.yellow(),
-            t.vcpus,
-            t.memory_gib,
-            t.storage_gib,
-            availability
-        ]);
+    match rt.block_on(client.validate_api_key()) {
+        Ok(()) => {
+            println!("API: {}", "reachable, key accepted".green());
+            Ok(())
+        }
+        Err(e) => {
+            match e.downcast_ref::<LambdaError>() {
+                Some(LambdaError::Unauthorized | LambdaError::Forbidden(_)) => {
+                    println!("API: {} ({})", "reachable, key rejected".red(), e)
+                }
+                Some(LambdaError::AccountSuspended { .. }) => {
+                    println!("API: {} ({})", "reachable, account inactive".red(), e)
+                }
+                _ => println!("API: {} ({})", "unreachable".red(), e),
+            }
+            Err(anyhow!("Environment check failed"))
+        }
     }
+}
 
-    table.printstd();
+fn export_instances(
+    rt: &Runtime,
+    client: &LambdaClient,
+    output: Option<&std::path::Path>,
+) -> Result<()> {
+    let instances = rt.block_on(client.list_running_instances())?;
+    let (spec, skipped) = FleetSpec::from_instances(&instances);
+
+    for s in &skipped {
+        eprintln!(
+            "{} Skipping instance {}: {}",
+            "Warning:".yellow(),
+            s.id.cyan(),
+            s.reason
+        );
+    }
+
+    let json = serde_json::to_string_pretty(&spec)?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, json + "\n")
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!(
+                "{} Exported {} instance(s) to {}",
+                "Success!".green().bold(),
+                spec.instances.len(),
+                path.display()
+            );
+        }
+        None => println!("{}", json),
+    }
     Ok(())
 }
 
-#[allow(clippy::too_many_arguments)]
-fn start_instance(
+fn import_instances(
     rt: &Runtime,
     client: &LambdaClient,
-    gpu: &str,
-    ssh: &str,
-    name: Option<&str>,
+    file: &std::path::Path,
+    resume: bool,
+    notifier: Option<&Notifier>,
+    yes: bool,
+) -> Result<()> {
+    let spec = FleetSpec::read(file)?;
+    if spec.instances.is_empty() {
+        println!("{}", "No instances in spec".yellow());
+        return Ok(());
+    }
+
+    let progress_path = ImportProgress::path_for(file);
+    let mut progress = match ImportProgress::load(&progress_path)? {
+        Some(progress) if resume => progress,
+        // Starting over would launch the already-launched instances a second time
+        Some(progress) => anyhow::bail!(
+            "An earlier import of {} stopped after {} of {} instance(s). Pass --resume to launch the rest, or delete {} to start over",
+            file.display(),
+            progress.launched.len(),
+            progress.intended,
+            progress_path.display()
+        ),
+        None if resume => anyhow::bail!("No interrupted import of {} to resume", file.display()),
+        None => ImportProgress::default(),
+    };
+    progress.intended = spec.instances.len();
+
+    if !yes {
+        require_terminal("launch billed instances", SKIP_PROMPT)?;
+        println!(
+            "This launches {} instance(s) from {}. Each is billed while running.",
+            progress.remaining(),
+            file.display()
+        );
+        if !ask_yes_no()? {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    let mut failed = 0;
+    let mut launched_now = Vec::new();
+    for (index, entry) in spec.instances.iter().enumerate() {
+        let label = entry.name.as_deref().unwrap_or(&entry.instance_type);
+        if progress.is_launched(index) {
+            println!("{} {} (launched earlier)", "Skipping".dimmed(), label);
+            continue;
+        }
+        let Some(ssh) = entry.ssh_key() else {
+            eprintln!("{} Skipping {}: no SSH keys", "Warning:".yellow(), label);
+            failed += 1;
+            continue;
+        };
+
+        match rt.block_on(client.launch_instance_with_options(
+            &entry.instance_type,
+            ssh,
+            &entry.launch_options(),
+        )) {
+            Ok(result) => {
+                println!(
+                    "{} {} -> {} in {}",
+                    "Launched".green(),
+                    label,
+                    result.instance_id.cyan(),
+                    result.region
+                );
+                launched_now.push(result.instance_id.clone());
+                progress.launched.insert(index, result.instance_id);
+                progress.save(&progress_path)?;
+            }
+            Err(e) => {
+                eprintln!("{} Failed to launch {}: {}", "Error:".red(), label, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed == 0 {
+        // Fully launched, so there is nothing left to resume
+        match std::fs::remove_file(&progress_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e)
+                    .with_context(|| format!("Failed to remove {}", progress_path.display()));
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(notifier) = notifier.filter(|_| !launched_now.is_empty()) {
+        notify_batch_ready(rt, client, notifier, launched_now);
+    }
+
+    if failed > 0 {
+        let hint = if progress.launched.is_empty() {
+            ""
+        } else {
+            "; rerun with --resume to retry them"
+        };
+        anyhow::bail!(
+            "{} of {} instance(s) were not launched{}",
+            failed,
+            spec.instances.len(),
+            hint
+        );
+    }
+    Ok(())
+}
+
+/// How long `import-instances --notify` waits for the batch before reporting it
+const BATCH_NOTIFY_TIMEOUT_SECS: u64 = 600;
+
+/// Wait for a batch of instances, then send one summary notification
+fn notify_batch_ready(
+    rt: &Runtime,
+    client: &LambdaClient,
+    notifier: &Notifier,
+    instance_ids: Vec<String>,
+) {
+    println!(
+        "Waiting for {} instance(s) to become active before notifying...",
+        instance_ids.len()
+    );
+    let mut batch = BatchTracker::new(instance_ids.clone());
+    rt.block_on(client.poll_many_until_active(
+        &instance_ids,
+        Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+        Duration::from_secs(BATCH_NOTIFY_TIMEOUT_SECS),
+        |id, result| {
+            if let Ok(instance) = result {
+                batch.record_ready(InstanceReadyMessage {
+                    instance_id: id.to_string(),
+                    instance_name: instance.name.clone(),
+                    ip: instance.ip.clone().unwrap_or_default(),
+                    gpu_type: instance.type_name().unwrap_or_default().to_string(),
+                    region: instance
+                        .region
+                        .as_ref()
+                        .and_then(|r| r.name.clone())
+                        .unwrap_or_default(),
+                    region_description: instance
+                        .region
+                        .as_ref()
+                        .and_then(|r| r.description.clone()),
+                    filesystems: instance.file_system_names.clone().unwrap_or_default(),
+                    ready_after: None,
+                });
+            }
+        },
+    ));
+
+    println!("{} Sending batch notification...", "Info:".blue());
+    let results = rt.block_on(notifier.send_batch(&batch.message()));
+    print_notify_results(results);
+}
+
+/// Exit code of `available` when the GPU has no capacity (errors exit 1)
+const UNAVAILABLE_EXIT_CODE: i32 = 3;
+
+fn available(
+    rt: &Runtime,
+    client: &LambdaClient,
+    out: &Output,
+    gpu: &str,
     region: Option<&str>,
-    filesystem: Option<&str>,
+) -> Result<()> {
+    let mut regions = rt.block_on(client.check_availability(gpu))?;
+    if let Some(region) = region {
+        let region = normalize_region(region);
+        regions.retain(|r| *r == region);
+    }
+
+    if out.is_json() {
+        out.print_json(&serde_json::json!({
+            "gpu": gpu,
+            "available": !regions.is_empty(),
+            "regions": regions,
+        }))?;
+    }
+    if regions.is_empty() {
+        std::process::exit(UNAVAILABLE_EXIT_CODE);
+    }
+    Ok(())
+}
+
+/// How long `serve-metrics` waits for a scraper to send its whole request
+const METRICS_READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Most header lines `serve-metrics` reads from one request
+const METRICS_MAX_HEADERS: usize = 64;
+/// Most bytes `serve-metrics` reads from one request
+const METRICS_MAX_REQUEST_BYTES: u64 = 16 * 1024;
+
+/// Serve `/metrics`, refreshing from the API on each scrape.
+///
+/// Each connection gets its own task, so a slow client can't hold up other
+/// scrapes. A failed refresh serves the last good snapshot with
+/// `lambda_api_up 0`, so gauges don't drop to zero while the API is unreachable.
+fn serve_metrics(rt: &Runtime, client: &LambdaClient, bind: &str, port: u16) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let listener = rt
+        .block_on(tokio::net::TcpListener::bind((bind, port)))
+        .with_context(|| format!("Failed to listen on {}:{}", bind, port))?;
+    println!(
+        "Serving metrics at {}",
+        format!("http://{}:{}/metrics", bind, port).cyan()
+    );
+
+    // Held across a refresh, so concurrent scrapes take turns replacing it
+    let snapshot = Arc::new(tokio::sync::Mutex::new(metrics::Snapshot::default()));
+    rt.block_on(async {
+        loop {
+            let mut stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    eprintln!("{} {}", "Warning:".yellow(), e);
+                    continue;
+                }
+            };
+            // Each scrape gets its own retry budget
+            let client = client.for_operation();
+            let snapshot = Arc::clone(&snapshot);
+            tokio::spawn(async move {
+                let path =
+                    tokio::time::timeout(METRICS_READ_TIMEOUT, read_request_path(&mut stream))
+                        .await
+                        .ok()
+                        .flatten();
+                let response = match path.as_deref() {
+                    Some("/metrics") => {
+                        let mut snapshot = snapshot.lock().await;
+                        let refreshed = async {
+                            let instances = client.list_running_instances().await?;
+                            let types = client.instance_types_by_name().await?;
+                            anyhow::Ok((instances, types))
+                        };
+                        let api_up = match refreshed.await {
+                            Ok((instances, types)) => {
+                                *snapshot = metrics::Snapshot {
+                                    instances,
+                                    types,
+                                    refreshed_at: Some(chrono::Utc::now().timestamp()),
+                                };
+                                true
+                            }
+                            Err(e) => {
+                                eprintln!("{} {}", "Warning:".yellow(), e);
+                                false
+                            }
+                        };
+                        http_response(
+                            "200 OK",
+                            "text/plain; version=0.0.4",
+                            &metrics::render(&snapshot, api_up),
+                        )
+                    }
+                    Some(_) => http_response("404 Not Found", "text/plain", "Not found\n"),
+                    None => http_response("400 Bad Request", "text/plain", "Bad request\n"),
+                };
+                if let Err(e) = stream.write_all(response.as_bytes()).await {
+                    eprintln!("{} {}", "Warning:".yellow(), e);
+                }
+            });
+        }
+    })
+}
+
+/// The path of a GET request, or `None` if the request isn't one or is too large
+async fn read_request_path(stream: &mut tokio::net::TcpStream) -> Option<String> {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+
+    let mut reader = BufReader::new(stream.take(METRICS_MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await.ok()?;
+    // Drain the headers so the client sees its whole request was read
+    let mut header = String::new();
+    let mut headers = 0;
+    while reader.read_line(&mut header).await.ok()? > 2 {
+        headers += 1;
+        if headers > METRICS_MAX_HEADERS {
+            return None;
+        }
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => Some(target.split('?').next()?.to_string()),
+        _ => None,
+    }
+}
+
+fn http_response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+fn price_history(out: &Output, gpu: &str) -> Result<()> {
+    let path = price_history::path_from_env().ok_or_else(|| {
+        anyhow!("Price history is off. Set LAMBDA_PRICE_HISTORY to a file path, then run `lambda list` to record prices")
+    })?;
+    let history = price_history::read_history(&path, gpu)?;
+
+    if out.is_json() {
+        return out.print_json(&history);
+    }
+
+    if history.is_empty() {
+        println!("{}", format!("No prices recorded for {}", gpu).yellow());
+        return Ok(());
+    }
+
+    let prices: Vec<i32> = history.iter().map(|s| s.price_cents).collect();
+    println!(
+        "{} {} ({} snapshots)",
+        gpu.green(),
+        price_history::sparkline(&prices),
+        history.len()
+    );
+
+    // One row per price change
+    let mut table = new_table(row!["Since", "Price ($/hr)"]);
+    let mut last_price = None;
+    for snapshot in &history {
+        if last_price != Some(snapshot.price_cents) {
+            table.add_row(row![
+                snapshot.timestamp,
+                format!("${:.2}", snapshot.price_cents as f64 / 100.0)
+            ]);
+            last_price = Some(snapshot.price_cents);
+        }
+    }
+    table.printstd();
+
+    Ok(())
+}
+
+fn list_instances(
+    rt: &Runtime,
+    client: &LambdaClient,
+    out: &Output,
+    price_unit: PriceUnit,
+    price_cents: bool,
+    columns: &[ListColumn],
+) -> Result<()> {
+    let types = rt.block_on(client.list_instance_types())?;
+    let summary = TypesSummary::new(&types);
+
+    if let Some(path) = price_history::path_from_env() {
+        let now = Local::now().to_rfc3339();
+        if let Err(e) = price_history::append_snapshots(&path, &types, &now) {
+            eprintln!("{} {}", "Warning:".yellow(), e);
+        }
+    }
+
+    if out.is_json() {
+        return out.print_json(&serde_json::json!({
+            "types": types,
+            "summary": summary,
+        }));
+    }
+
+    // Cents per day and month are still whole numbers, so only dollars need rounding
+    let price = |cents: i32| {
+        let cents = cents as f64 * price_unit.hours();
+        if price_cents {
+            format!("{}", cents.round() as i64)
+        } else {
+            format!("${:.2}", cents / 100.0)
+        }
+    };
+
+    let price_header = format!(
+        "Price ({}/{})",
+        if price_cents { "cents" } else { "$" },
+        price_unit.label()
+    );
+    instance_types_table(&types, columns, &price_header, price).printstd();
+
+    let range = match (
+        summary.cheapest_available_cents,
+        summary.priciest_available_cents,
+    ) {
+        (Some(low), Some(high)) => format!(
+            ", from {} to {} {}per {}",
+            price(low).yellow(),
+            price(high).yellow(),
+            if price_cents { "cents " } else { "" },
+            price_unit.label()
+        ),
+        _ => String::new(),
+    };
+    println!(
+        "{} instance types, {} with capacity{}",
+        summary.total, summary.available, range
+    );
+    Ok(())
+}
+
+/// `lambda list` table of `types`, with prices formatted by `price`
+fn instance_types_table(
+    types: &[InstanceTypeData],
+    columns: &[ListColumn],
+    price_header: &str,
+    price: impl Fn(i32) -> String,
+) -> Table {
+    let columns = columns_or_all(columns);
+    let mut table = new_table(
+        columns
+            .iter()
+            .map(|column| {
+                Cell::new(match column {
+                    ListColumn::Type => "Instance Type",
+                    ListColumn::Description => "Description",
+                    ListColumn::Price => price_header,
+                    ListColumn::Vcpus => "vCPUs",
+                    ListColumn::Memory => "Memory (GiB)",
+                    ListColumn::Storage => "Storage (GiB)",
+                    ListColumn::Regions => "Available Regions",
+                })
+            })
+            .collect(),
+    );
+
+    for t in types {
+        let available = !t.regions_available.is_empty();
+        let cell = |column: &ListColumn| match column {
+            ListColumn::Type if available => t.name.green().to_string(),
+            ListColumn::Type => t.name.dimmed().to_string(),
+            ListColumn::Description => t.description.clone(),
+            ListColumn::Price => price(t.price_cents_per_hour).yellow().to_string(),
+            ListColumn::Vcpus => t.vcpus.to_string(),
+            ListColumn::Memory => t.memory_gib.to_string(),
+            ListColumn::Storage => t.storage_gib.to_string(),
+            ListColumn::Regions if available => t.regions_available.join(", ").blue().to_string(),
+            ListColumn::Regions => "None".red().to_string(),
+        };
+        table.add_row(columns.iter().map(|c| Cell::new(&cell(c))).collect());
+    }
+
+    table
+}
+
+/// Market snapshot over the listed instance types
+#[derive(Serialize)]
+struct TypesSummary {
+    total: usize,
+    /// Types with capacity in at least one region
+    available: usize,
+    cheapest_available_cents: Option<i32>,
+    priciest_available_cents: Option<i32>,
+}
+
+impl TypesSummary {
+    fn new(types: &[InstanceTypeData]) -> Self {
+        let available_prices: Vec<i32> = types
+            .iter()
+            .filter(|t| !t.regions_available.is_empty())
+            .map(|t| t.price_cents_per_hour)
+            .collect();
+
+        Self {
+            total: types.len(),
+            available: available_prices.len(),
+            cheapest_available_cents: available_prices.iter().min().copied(),
+            priciest_available_cents: available_prices.iter().max().copied(),
+        }
+    }
+}
+
+/// Fail if a name is required (by `--name-required` or the client) and `name` is missing
+fn check_name_required(client: &LambdaClient, flag: bool, name: Option<&str>) -> Result<()> {
+    if (flag || client.requires_name()) && name.is_none_or(str::is_empty) {
+        return Err(LambdaError::NameRequired.into());
+    }
+    Ok(())
+}
+
+/// Warn (or with `strict`, fail) if a running instance is already called `name`
+fn check_name_unused(rt: &Runtime, client: &LambdaClient, name: &str, strict: bool) -> Result<()> {
+    let instances = match rt.block_on(client.list_running_instances()) {
+        Ok(instances) => instances,
+        Err(e) if !strict => {
+            eprintln!(
+                "{} Couldn't check whether '{}' is in use: {}",
+                "Warning:".yellow(),
+                name,
+                e
+            );
+            return Ok(());
+        }
+        Err(e) => return Err(e.context("Failed to check instance names (--strict-names)")),
+    };
+    let taken: Vec<&str> = instances
+        .iter()
+        .filter(|inst| inst.name.as_deref() == Some(name))
+        .filter_map(|inst| inst.id.as_deref())
+        .collect();
+    if taken.is_empty() {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Instance name '{}' is already used by {}",
+        name,
+        taken.join(", ")
+    );
+    if strict {
+        anyhow::bail!("{} (omit --strict-names to launch anyway)", message);
+    }
+    eprintln!("{} {}; launching anyway", "Warning:".yellow(), message);
+    Ok(())
+}
+
+/// Whether a launch failed only because the capacity wasn't there
+fn is_no_capacity(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<LambdaError>()
+        .is_some_and(LambdaError::is_no_capacity)
+}
+
+/// Launch the first of `gpus` with capacity and wait for it to be ready
+#[allow(clippy::too_many_arguments)]
+fn start_instance(
+    rt: &Runtime,
+    client: &LambdaClient,
+    gpus: &[&str],
+    ssh: &str,
+    launch: &LaunchOptions,
     no_notify: bool,
+    poll_interval: u64,
+    open: bool,
 ) -> Result<()> {
+    let name = launch.name.as_deref();
     // Auto-enable notifications if env vars are configured (unless --no-notify)
     let notifier = if no_notify {
         None
     } else {
         NotifyConfig::from_env().map(|config| {
-            println!(
+            progress!(
                 "{} Notifications enabled for: {}",
                 "Info:".blue(),
                 config.configured_channels().join(", ")
             );
-            Notifier::new(config)
+            Notifier::new(config).with_http_client(client.http_client().clone())
         })
     };
 
-    let fs_info = filesystem
+    let fs_info = launch
+        .filesystem
+        .as_deref()
         .map(|f| format!(" with filesystem '{}'", f.magenta()))
         .unwrap_or_default();
-    println!(
-        "Launching {} {}{}...",
+
+    let mut launched = None;
+    for (i, gpu) in gpus.iter().enumerate() {
+        progress!(
+            "Launching {} {}{}...",
+            gpu.green(),
+            name.map(|n| format!("as '{}'", n.cyan()))
+                .unwrap_or_default(),
+            fs_info
+        );
+        let launched_at = Instant::now();
+        match rt.block_on(client.launch_instance_with_options(gpu, ssh, launch)) {
+            Ok(result) => {
+                launched = Some((*gpu, result, launched_at));
+                break;
+            }
+            Err(e) if i + 1 < gpus.len() && is_no_capacity(&e) => {
+                progress!(
+                    "{} {}; trying the next fallback",
+                    "No capacity:".yellow(),
+                    e
+                );
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    let (gpu, mut result, launched_at) = launched.expect("start always has a GPU type");
+
+    progress!(
+        "{} Instance {} ({}) launched in region {}",
+        "Success!".green().bold(),
+        result.instance_id.cyan(),
         gpu.green(),
-        name.map(|n| format!("as '{}'", n.cyan()))
-            .unwrap_or_default(),
-        fs_info
+        result.region.blue()
     );
+    progress!("Waiting for instance to become active...");
+
+    let wait = WaitOptions {
+        ready_when: ReadyWhen::IpAssigned,
+        max_wait: Duration::from_secs(300),
+        poll_interval: Duration::from_secs(poll_interval),
+        require_stable: None,
+        booting_warning: booting_warning_from_env()?,
+    };
+
+    let Some(instance) = wait_for_instance(rt, client, &result.instance_id, &wait)? else {
+        progress!(
+            "{} Instance may still be starting. Check status with: lambda running",
+            "Timeout:".yellow()
+        );
+        return Ok(());
+    };
+
+    let ip = instance.ip.unwrap_or_default();
+    result.set_ip(&ip);
+    let ready_after = launched_at.elapsed();
+    progress!(
+        "{} Instance is ready in {}! SSH: {}",
+        "Ready!".green().bold(),
+        format_elapsed(ready_after),
+        result.ssh_command.as_deref().unwrap_or_default().cyan()
+    );
+
+    if open {
+        match instance.jupyter_url.as_deref() {
+            Some(url) if open_in_browser(url) => progress!("{} Opened {}", "Jupyter:".blue(), url),
+            Some(url) => progress!(
+                "{} Couldn't open a browser; visit {}",
+                "Jupyter:".blue(),
+                url.cyan()
+            ),
+            None => progress!(
+                "{} The API hasn't reported a Jupyter URL yet; check later with: lambda describe -i {}",
+                "Jupyter:".blue(),
+                result.instance_id
+            ),
+        }
+    }
+
+    // Send notification if configured
+    if let Some(ref notifier) = notifier {
+        let msg = InstanceReadyMessage {
+            instance_id: result.instance_id.clone(),
+            instance_name: name.map(String::from),
+            ip,
+            gpu_type: gpu.to_string(),
+            region: result.region.clone(),
+            region_description: instance.region.and_then(|r| r.description),
+            filesystems: instance.file_system_names.unwrap_or_default(),
+            ready_after: Some(ready_after),
+        };
+
+        progress!("{} Sending notifications...", "Info:".blue());
+        let results = rt.block_on(notifier.send_all(&msg));
+        print_notify_results(results);
+    }
+
+    Ok(())
+}
+
+/// Open `url` with the platform's default handler; returns whether it launched
+fn open_in_browser(url: &str) -> bool {
+    use std::process::{Command, Stdio};
+
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// When a polled instance is considered ready
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ReadyWhen {
+    /// An IP has been assigned (SSH-able, even if not yet reported `active`)
+    IpAssigned,
+    /// The instance reports `active`
+    Active,
+}
+
+struct WaitOptions {
+    ready_when: ReadyWhen,
+    max_wait: Duration,
+    poll_interval: Duration,
+    /// How long the instance must stay `active` before it is declared ready
+    require_stable: Option<Duration>,
+    /// Warn if the instance is still `booting` after this long
+    booting_warning: Option<Duration>,
+}
+
+/// Default for `LAMBDA_BOOTING_WARNING_SECS`
+const DEFAULT_BOOTING_WARNING_SECS: u64 = 180;
+
+/// Booting-warning threshold from `LAMBDA_BOOTING_WARNING_SECS` (`0` disables it)
+fn booting_warning_from_env() -> Result<Option<Duration>> {
+    let secs = match std::env::var("LAMBDA_BOOTING_WARNING_SECS") {
+        Ok(secs) if !secs.is_empty() => secs.trim().parse().with_context(|| {
+            format!(
+                "Invalid LAMBDA_BOOTING_WARNING_SECS '{}' (expected seconds)",
+                secs
+            )
+        })?,
+        _ => DEFAULT_BOOTING_WARNING_SECS,
+    };
+    Ok((secs > 0).then(|| Duration::from_secs(secs)))
+}
+
+/// Poll an instance until it is ready.
+///
+/// Returns `Ok(None)` if `max_wait` elapses first, and an error if the instance
+/// enters a terminal state.
+fn wait_for_instance(
+    rt: &Runtime,
+    client: &LambdaClient,
+    instance_id: &str,
+    opts: &WaitOptions,
+) -> Result<Option<Instance>> {
+    let start_time = Instant::now();
+    // When the current run of consecutive `active` observations began
+    let mut active_since: Option<Instant> = None;
+    let mut warned_booting = false;
+
+    loop {
+        if start_time.elapsed() > opts.max_wait {
+            progress!();
+            return Ok(None);
+        }
+
+        std::thread::sleep(opts.poll_interval);
+
+        match rt.block_on(client.get_instance(instance_id)) {
+            Ok(instance) => {
+                let status = instance.status.as_ref();
+                progress_inline!(
+                    "\r{} Status: {}    ",
+                    "Polling...".dimmed(),
+                    status.map_or("unknown", InstanceStatus::as_str).yellow()
+                );
+
+                if status.is_some_and(InstanceStatus::is_failed) {
+                    progress!();
+                    return Err(anyhow!(
+                        "Instance entered {} state",
+                        instance.status_display()
+                    ));
+                }
+
+                if let Some(threshold) = opts.booting_warning {
+                    if status == Some(&InstanceStatus::Booting)
+                        && !warned_booting
+                        && start_time.elapsed() >= threshold
+                    {
+                        warned_booting = true;
+                        eprintln!(
+                            "\n{} Instance {} has been booting for over {}s, longer than usual. \
+                             Check it in the Lambda dashboard, or terminate it with `lambda stop -i {}` \
+                             and try again. Still waiting...",
+                            "WARNING:".red().bold(),
+                            instance_id,
+                            threshold.as_secs(),
+                            instance_id
+                        );
+                    }
+                }
+
+                let ready = match opts.ready_when {
+                    // Ready when IP is available (don't wait for "active" status)
+                    ReadyWhen::IpAssigned => instance.ip.is_some(),
+                    ReadyWhen::Active if status == Some(&InstanceStatus::Active) => {
+                        let since = *active_since.get_or_insert_with(Instant::now);
+                        opts.require_stable
+                            .is_none_or(|stable| since.elapsed() >= stable)
+                    }
+                    ReadyWhen::Active => {
+                        if active_since.take().is_some() {
+                            progress!(
+                                "\r{} Instance left active state, restarting stability window",
+                                "Warning:".yellow()
+                            );
+                        }
+                        false
+                    }
+                };
+
+                if ready {
+                    progress!();
+                    return Ok(Some(instance));
+                }
+            }
+            Err(e) => {
+                progress_inline!("\r{} Waiting for instance...    ", "Polling...".dimmed());
+                eprintln!("\nWarning: {}", e);
+            }
+        }
+    }
+}
+
+/// What is left of `timeout` seconds counted from `launched`, for `wait --resume-from`
+fn remaining_timeout(launched: chrono::DateTime<chrono::Utc>, timeout: u64) -> Result<u64> {
+    // A launch time in the future (clock skew) counts as just launched
+    let elapsed = (chrono::Utc::now() - launched).num_seconds().max(0) as u64;
+    let remaining = timeout.saturating_sub(elapsed);
+    if remaining == 0 {
+        anyhow::bail!(
+            "The {}s timeout already ran out: {}s have passed since {}",
+            timeout,
+            elapsed,
+            launched.to_rfc3339()
+        );
+    }
+    println!(
+        "Resuming: {}s since launch, waiting up to {}s more",
+        elapsed, remaining
+    );
+    Ok(remaining)
+}
+
+fn wait_command(
+    rt: &Runtime,
+    client: &LambdaClient,
+    instance_ids: &[String],
+    timeout: u64,
+    require_stable: Option<u64>,
+    poll_interval: u64,
+) -> Result<()> {
+    let instance_id = match instance_ids {
+        [id] => id,
+        _ if require_stable.is_some() => {
+            anyhow::bail!("--require-stable supports only a single instance")
+        }
+        _ => return wait_many(rt, client, instance_ids, timeout, poll_interval),
+    };
+
+    let wait = WaitOptions {
+        ready_when: ReadyWhen::Active,
+        max_wait: Duration::from_secs(timeout),
+        poll_interval: Duration::from_secs(poll_interval),
+        require_stable: require_stable.map(Duration::from_secs),
+        booting_warning: booting_warning_from_env()?,
+    };
+
+    match require_stable {
+        Some(secs) => println!(
+            "Waiting for instance {} to stay active for {}s...",
+            instance_id.cyan(),
+            secs
+        ),
+        None => println!(
+            "Waiting for instance {} to become active...",
+            instance_id.cyan()
+        ),
+    }
+
+    match wait_for_instance(rt, client, instance_id, &wait)? {
+        Some(instance) => {
+            println!(
+                "{} Instance {} is active{}",
+                "Ready!".green().bold(),
+                instance_id.cyan(),
+                instance
+                    .ip
+                    .map(|ip| format!(" (IP: {})", ip.blue()))
+                    .unwrap_or_default()
+            );
+            Ok(())
+        }
+        None => Err(anyhow!(
+            "Timed out after {}s waiting for instance {}",
+            timeout,
+            instance_id
+        )),
+    }
+}
+
+/// Wait for several instances at once, reporting each as soon as it settles
+fn wait_many(
+    rt: &Runtime,
+    client: &LambdaClient,
+    instance_ids: &[String],
+    timeout: u64,
+    poll_interval: u64,
+) -> Result<()> {
+    println!(
+        "Waiting for {} instances to become active...",
+        instance_ids.len()
+    );
+
+    let results = rt.block_on(client.poll_many_until_active(
+        instance_ids,
+        Duration::from_secs(poll_interval),
+        Duration::from_secs(timeout),
+        |id, result| match result {
+            Ok(instance) => println!(
+                "  {} {} is active{}",
+                "✓".green(),
+                id.cyan(),
+                instance
+                    .ip
+                    .as_ref()
+                    .map(|ip| format!(" (IP: {})", ip.blue()))
+                    .unwrap_or_default()
+            ),
+            Err(e) => println!("  {} {}", "✗".red(), e),
+        },
+    ));
+
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+    if failed > 0 {
+        anyhow::bail!(
+            "{} of {} instances did not become active",
+            failed,
+            results.len()
+        );
+    }
+    println!(
+        "{} All {} instances are active",
+        "Ready!".green().bold(),
+        results.len()
+    );
+    Ok(())
+}
+
+/// Print each channel's result; returns `false` if every channel failed
+fn print_notify_results(results: Vec<(&'static str, Result<()>)>) -> bool {
+    let (succeeded, failed) = notify::summarize(&results);
+    for (channel, result) in results {
+        match result {
+            Ok(()) => progress!("  {} {} notification sent", "✓".green(), channel),
+            Err(e) => progress!("  {} {} notification failed: {}", "✗".red(), channel, e),
+        }
+    }
+
+    if succeeded == 0 && failed > 0 {
+        progress!(
+            "{} No notification was delivered; check your LAMBDA_NOTIFY_* settings",
+            "Warning:".yellow()
+        );
+        return false;
+    }
+    true
+}
+
+fn notify_test(rt: &Runtime) -> Result<()> {
+    let config = NotifyConfig::from_env().ok_or_else(|| {
+        anyhow!("No notification channels configured. Set LAMBDA_NOTIFY_* environment variables")
+    })?;
+
+    println!(
+        "{} Sending test notification to: {}",
+        "Info:".blue(),
+        config.configured_channels().join(", ")
+    );
+
+    let msg = InstanceReadyMessage {
+        instance_id: "test-instance".to_string(),
+        instance_name: Some("lambda-cli-test".to_string()),
+        ip: "192.0.2.1".to_string(),
+        gpu_type: "gpu_1x_a10".to_string(),
+        region: "us-east-1".to_string(),
+        region_description: Some("US East (Virginia)".to_string()),
+        filesystems: vec!["lambda-cli-test-fs".to_string()],
+        ready_after: Some(Duration::from_secs(134)),
+    };
+
+    let notifier = Notifier::new(config);
+    // Test messages bypass deduplication so they can be sent repeatedly
+    let results = rt.block_on(notifier.send_all_with_options(&msg, true));
+    if !print_notify_results(results) {
+        return Err(anyhow!("All notification channels failed"));
+    }
+    Ok(())
+}
+
+fn notify_status(out: &Output) -> Result<()> {
+    let status = ChannelStatus::from_env();
+    if out.is_json() {
+        return out.print_json(&status);
+    }
+
+    for channel in &status {
+        let mark = if channel.configured {
+            "✓".green()
+        } else {
+            "-".dimmed()
+        };
+        println!("{} {:<9} {}", mark, channel.channel, channel.detail);
+    }
+    if !status.iter().any(|c| c.configured) {
+        println!(
+            "\n{}",
+            "No channels would fire. Set LAMBDA_NOTIFY_* environment variables".yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Effective settings for `lambda config`
+#[derive(Serialize)]
+struct ConfigReport {
+    api_base_url: &'static str,
+    /// Variable (or `stdin`) the API key is read from, if any
+    api_key_source: Option<&'static str>,
+    request_timeout_secs: u64,
+    settings: Vec<Setting>,
+    notify_channels: Vec<ChannelStatus>,
+}
+
+fn show_config(cli: &Cli, out: &Output) -> Result<()> {
+    let api_key_source = if cli.api_key_stdin {
+        Some("stdin")
+    } else {
+        settings::api_key_source(|name| std::env::var(name).ok())
+    };
+    let report = ConfigReport {
+        api_base_url: API_BASE_URL,
+        api_key_source,
+        request_timeout_secs: DEFAULT_TIMEOUT_SECS,
+        settings: Setting::from_env(),
+        notify_channels: ChannelStatus::from_env(),
+    };
+    if out.is_json() {
+        return out.print_json(&report);
+    }
+
+    println!("API base URL:    {}", report.api_base_url);
+    match report.api_key_source {
+        Some(source) => println!("API key source:  {}", source),
+        None => println!("API key source:  {}", "none".yellow()),
+    }
+    println!("Request timeout: {}s", report.request_timeout_secs);
+
+    println!();
+    let width = report
+        .settings
+        .iter()
+        .map(|s| s.name.len())
+        .max()
+        .unwrap_or(0);
+    for setting in &report.settings {
+        let line = format!("{:<width$}  {}", setting.name, setting.value);
+        if setting.source == "env" {
+            println!("{}", line);
+        } else {
+            println!("{}", line.dimmed());
+        }
+    }
+
+    println!();
+    for channel in &report.notify_channels {
+        let state = if channel.configured { "on" } else { "off" };
+        println!(
+            "Notify {:<9} {} ({})",
+            channel.channel, state, channel.detail
+        );
+    }
+    Ok(())
+}
+
+/// How long `selftest` and `run` wait for a new instance to become active
+const ACTIVE_TIMEOUT_SECS: u64 = 600;
+/// How long `selftest` and `run` wait for port 22 once the instance is active
+const SSH_PORT_TIMEOUT_SECS: u64 = 180;
+
+/// Terminates an instance when dropped, so a failed phase can't leave it billing
+struct TerminateGuard<'a> {
+    rt: &'a Runtime,
+    client: &'a LambdaClient,
+    instance_id: Option<String>,
+}
+
+impl TerminateGuard<'_> {
+    /// Terminate now, reporting any failure instead of deferring to drop
+    fn terminate(&mut self) -> Result<()> {
+        match self.instance_id.take() {
+            Some(id) => self.rt.block_on(self.client.terminate_instance(&id)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for TerminateGuard<'_> {
+    fn drop(&mut self) {
+        let Some(id) = self.instance_id.take() else {
+            return;
+        };
+        eprintln!(
+            "{} Terminating test instance {}...",
+            "Cleanup:".yellow(),
+            id
+        );
+        if let Err(e) = self.rt.block_on(self.client.terminate_instance(&id)) {
+            eprintln!(
+                "{} Failed to terminate {}: {}. Run `lambda stop -i {}` to avoid charges",
+                "Error:".red().bold(),
+                id,
+                e,
+                id
+            );
+        }
+    }
+}
+
+/// Run `body`, terminating `instance_id` and exiting if Ctrl+C arrives first.
+///
+/// Ctrl+C otherwise kills the process before any `TerminateGuard` can run,
+/// leaving the instance billing.
+fn terminate_on_interrupt<T>(
+    rt: &Runtime,
+    client: &LambdaClient,
+    instance_id: &str,
+    body: impl FnOnce() -> T,
+) -> T {
+    let done = tokio::sync::Notify::new();
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            rt.block_on(async {
+                tokio::select! {
+                    Ok(()) = tokio::signal::ctrl_c() => {
+                        eprintln!(
+                            "\n{} Interrupted; terminating instance {}...",
+                            "Cleanup:".yellow(),
+                            instance_id
+                        );
+                        match client.terminate_instance(instance_id).await {
+                            Ok(()) => eprintln!("{} Terminated {}", "Cleanup:".yellow(), instance_id),
+                            Err(e) => eprintln!(
+                                "{} Failed to terminate {}: {}. Run `lambda stop -i {}` to avoid charges",
+                                "Error:".red().bold(),
+                                instance_id,
+                                e,
+                                instance_id
+                            ),
+                        }
+                        std::process::exit(130);
+                    }
+                    _ = done.notified() => {}
+                }
+            })
+        });
+        // Created first, so the watcher is released even if `body` panics
+        let _finished = NotifyOnDrop(&done);
+        body()
+    })
+}
+
+/// Wakes a waiter when dropped, however its scope ends
+struct NotifyOnDrop<'a>(&'a tokio::sync::Notify);
+
+impl Drop for NotifyOnDrop<'_> {
+    fn drop(&mut self) {
+        // Stores a permit if the waiter isn't waiting yet, so it can't miss this
+        self.0.notify_one();
+    }
+}
+
+fn selftest(
+    rt: &Runtime,
+    client: &LambdaClient,
+    gpu: Option<&str>,
+    ssh: &str,
+    yes: bool,
+) -> Result<()> {
+    let gpu = match gpu {
+        Some(gpu) => gpu.to_string(),
+        None => rt
+            .block_on(client.list_instance_types())?
+            .into_iter()
+            .filter(|t| !t.regions_available.is_empty())
+            .min_by_key(|t| t.price_cents_per_hour)
+            .map(|t| t.name)
+            .ok_or_else(|| anyhow!("No instance type has capacity right now; try again later"))?,
+    };
+
+    if !yes {
+        require_terminal("launch a billed test instance", SKIP_PROMPT)?;
+        println!(
+            "This launches a {} instance, checks it, then terminates it. It is billed while running.",
+            gpu.green()
+        );
+        if !ask_yes_no()? {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    let mut timings: Vec<(&str, Duration)> = Vec::new();
+
+    let phase = Instant::now();
+    println!("{} Launching {}...", "[1/4]".dimmed(), gpu.green());
+    let launched =
+        rt.block_on(client.launch_instance_with_options(&gpu, ssh, &LaunchOptions::default()))?;
+    let mut guard = TerminateGuard {
+        rt,
+        client,
+        instance_id: Some(launched.instance_id.clone()),
+    };
+    timings.push(("Launch", phase.elapsed()));
+    println!(
+        "      Instance {} in {}",
+        launched.instance_id.cyan(),
+        launched.region.blue()
+    );
+
+    terminate_on_interrupt(rt, client, &launched.instance_id, || {
+        selftest_phases(rt, client, &launched.instance_id, &mut guard, &mut timings)
+    })?;
+
+    let mut table = new_table(row!["Phase", "Time"]);
+    for (name, elapsed) in &timings {
+        table.add_row(row![name, format!("{:.1}s", elapsed.as_secs_f64())]);
+    }
+    table.printstd();
+    println!("{} Self-test passed", "Success!".green().bold());
+    Ok(())
+}
+
+/// `selftest` from launch to termination
+fn selftest_phases(
+    rt: &Runtime,
+    client: &LambdaClient,
+    instance_id: &str,
+    guard: &mut TerminateGuard,
+    timings: &mut Vec<(&str, Duration)>,
+) -> Result<()> {
+    let phase = Instant::now();
+    println!("{} Waiting for active...", "[2/4]".dimmed());
+    let wait = WaitOptions {
+        ready_when: ReadyWhen::Active,
+        max_wait: Duration::from_secs(ACTIVE_TIMEOUT_SECS),
+        poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+        require_stable: None,
+        booting_warning: booting_warning_from_env()?,
+    };
+    let instance = wait_for_instance(rt, client, instance_id, &wait)?.ok_or_else(|| {
+        anyhow!(
+            "Instance did not become active within {} seconds",
+            ACTIVE_TIMEOUT_SECS
+        )
+    })?;
+    timings.push(("Active", phase.elapsed()));
+
+    let phase = Instant::now();
+    let ip = instance
+        .ip
+        .ok_or_else(|| anyhow!("Instance is active but has no IP address"))?;
+    println!("{} Checking SSH port on {}...", "[3/4]".dimmed(), ip.blue());
+    wait_for_ssh_port(&ip, Duration::from_secs(SSH_PORT_TIMEOUT_SECS))?;
+    timings.push(("SSH reachable", phase.elapsed()));
+
+    let phase = Instant::now();
+    println!("{} Terminating...", "[4/4]".dimmed());
+    guard.terminate()?;
+    timings.push(("Terminate", phase.elapsed()));
+    Ok(())
+}
+
+struct RunOptions<'a> {
+    command: &'a str,
+    identity: Option<&'a std::path::Path>,
+    terminate_on_exit: bool,
+}
+
+fn run_remote(
+    rt: &Runtime,
+    client: &LambdaClient,
+    gpu: &str,
+    ssh: &str,
+    launch: &LaunchOptions,
+    run: &RunOptions,
+) -> Result<()> {
+    println!("Launching {}...", gpu.green());
+    let launched = rt.block_on(client.launch_instance_with_options(gpu, ssh, launch))?;
+    // With --terminate-on-exit, every path out of here (including errors) terminates
+    let _guard = TerminateGuard {
+        rt,
+        client,
+        instance_id: run.terminate_on_exit.then(|| launched.instance_id.clone()),
+    };
+    println!(
+        "Instance {} in {}",
+        launched.instance_id.cyan(),
+        launched.region.blue()
+    );
+
+    if run.terminate_on_exit {
+        terminate_on_interrupt(rt, client, &launched.instance_id, || {
+            run_on_instance(rt, client, &launched.instance_id, run)
+        })
+    } else {
+        run_on_instance(rt, client, &launched.instance_id, run)
+    }
+}
+
+/// `run` once the instance is launched: wait for it, then run the command over SSH
+fn run_on_instance(
+    rt: &Runtime,
+    client: &LambdaClient,
+    instance_id: &str,
+    run: &RunOptions,
+) -> Result<()> {
+    let wait = WaitOptions {
+        ready_when: ReadyWhen::Active,
+        max_wait: Duration::from_secs(ACTIVE_TIMEOUT_SECS),
+        poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+        require_stable: None,
+        booting_warning: booting_warning_from_env()?,
+    };
+    let instance = wait_for_instance(rt, client, instance_id, &wait)?.ok_or_else(|| {
+        anyhow!(
+            "Instance did not become active within {} seconds",
+            ACTIVE_TIMEOUT_SECS
+        )
+    })?;
+    let ip = instance
+        .ip
+        .ok_or_else(|| anyhow!("Instance is active but has no IP address"))?;
+    wait_for_ssh_port(&ip, Duration::from_secs(SSH_PORT_TIMEOUT_SECS))?;
+
+    println!("{} ssh ubuntu@{} {}", "Running:".blue(), ip, run.command);
+    let mut ssh_cmd = std::process::Command::new("ssh");
+    ssh_cmd.args(["-o", "StrictHostKeyChecking=accept-new"]);
+    if let Some(identity) = run.identity {
+        ssh_cmd.arg("-i").arg(identity);
+    }
+    ssh_cmd.arg(format!("ubuntu@{}", ip)).arg(run.command);
+
+    let status = ssh_cmd.status().context("Failed to run ssh")?;
+
+    if !run.terminate_on_exit {
+        println!(
+            "Instance {} is still running; stop it with `lambda stop -i {}`",
+            instance_id.cyan(),
+            instance_id
+        );
+    }
+
+    if !status.success() {
+        return Err(anyhow!(
+            "Remote command failed ({})",
+            status
+                .code()
+                .map(|c| format!("exit code {}", c))
+                .unwrap_or_else(|| "terminated by signal".to_string())
+        ));
+    }
+    Ok(())
+}
+
+/// Retry a TCP connection to port 22 until it succeeds or `max_wait` elapses
+fn wait_for_ssh_port(ip: &str, max_wait: Duration) -> Result<()> {
+    let addr = std::net::SocketAddr::new(
+        ip.parse()
+            .map_err(|_| anyhow!("Invalid instance IP '{}'", ip))?,
+        22,
+    );
+    let start = Instant::now();
+
+    loop {
+        let result = std::net::TcpStream::connect_timeout(&addr, Duration::from_secs(5));
+        match result {
+            Ok(_) => return Ok(()),
+            Err(e) if start.elapsed() > max_wait => {
+                return Err(anyhow!(
+                    "SSH port on {} not reachable after {} seconds: {}",
+                    ip,
+                    max_wait.as_secs(),
+                    e
+                ))
+            }
+            Err(_) => std::thread::sleep(Duration::from_secs(5)),
+        }
+    }
+}
+
+/// Terminate `instance_ids` with a single API request and report each one
+fn stop_instances(
+    rt: &Runtime,
+    client: &LambdaClient,
+    instance_ids: &[String],
+    dry_run: bool,
+) -> Result<()> {
+    if dry_run {
+        for instance_id in instance_ids {
+            let instance = rt.block_on(client.get_instance(instance_id))?;
+            println!(
+                "{} Would terminate instance {} ({}, {} in {}, {})",
+                "Dry run:".yellow().bold(),
+                instance_id.cyan(),
+                instance.name.as_deref().unwrap_or("unnamed"),
+                instance.type_name().unwrap_or("unknown type"),
+                instance
+                    .region
+                    .as_ref()
+                    .and_then(|r| r.name.as_deref())
+                    .unwrap_or("unknown region"),
+                instance.status_display()
+            );
+        }
+        return Ok(());
+    }
+
+    match instance_ids {
+        [instance_id] => println!("Terminating instance {}...", instance_id.cyan()),
+        _ => println!("Terminating {} instances...", instance_ids.len()),
+    }
+
+    let terminated = rt.block_on(client.terminate_instances(instance_ids))?;
+
+    let mut missing = 0;
+    for instance_id in instance_ids {
+        if terminated.contains(instance_id) {
+            println!(
+                "{} Instance {} terminated",
+                "Success!".green().bold(),
+                instance_id.cyan()
+            );
+        } else {
+            missing += 1;
+            println!(
+                "{} Instance {} was not reported as terminated",
+                "Warning:".yellow().bold(),
+                instance_id.cyan()
+            );
+        }
+    }
+    if missing > 0 {
+        anyhow::bail!(
+            "{} of {} instance(s) were not reported as terminated; check `lambda running`",
+            missing,
+            instance_ids.len()
+        );
+    }
+    Ok(())
+}
+
+fn describe_instance(
+    rt: &Runtime,
+    client: &LambdaClient,
+    out: &Output,
+    instance_id: &str,
+) -> Result<()> {
+    let body = rt.block_on(client.get_instance_raw(instance_id))?;
+    // Reformat when the body is JSON, but never drop what the API sent
+    match serde_json::from_str::<serde_json::Value>(&body) {
+        Ok(value) if out.is_json() => out.print_json(&value)?,
+        Ok(value) => println!("{}", serde_json::to_string_pretty(&value)?),
+        Err(_) => println!("{}", body),
+    }
+    Ok(())
+}
 
-    let result =
-        rt.block_on(client.launch_instance_with_filesystem(gpu, ssh, name, region, filesystem))?;
+/// How `lambda running` orders and trims its output
+struct RunningView {
+    sort: Option<RunningSort>,
+    limit: Option<usize>,
+    /// Print one templated line per instance instead of a table
+    template: Option<Template>,
+    count_only: bool,
+    /// Table columns in order; empty means all
+    columns: Vec<RunningColumn>,
+}
 
-    println!(
-        "{} Instance {} launched in region {}",
-        "Success!".green().bold(),
-        result.instance_id.cyan(),
-        result.region.blue()
-    );
-    println!("Waiting for instance to become active...");
+/// Which running instances `lambda running` shows; unset fields match anything
+struct InstanceFilter<'a> {
+    gpu: Option<&'a str>,
+    ssh_key: Option<&'a str>,
+}
 
-    // Poll for instance to become ready
-    let start_time = Instant::now();
-    let max_wait = Duration::from_secs(300);
-    let poll_interval = Duration::from_secs(10);
+impl InstanceFilter<'_> {
+    fn matches(&self, instance: &Instance) -> bool {
+        self.gpu.is_none_or(|gpu| instance.type_name() == Some(gpu))
+            && self.ssh_key.is_none_or(|key| {
+                instance
+                    .ssh_key_names
+                    .as_ref()
+                    .is_some_and(|keys| keys.iter().any(|k| k == key))
+            })
+    }
+}
+
+/// `running --watch`: list again every `interval` until interrupted.
+///
+/// JSON output is one compact snapshot per line (NDJSON), flushed after each
+/// refresh so a downstream reader sees it straight away. A failed refresh is
+/// reported on stderr and the next one is tried as usual.
+fn watch_running_instances(
+    rt: &Runtime,
+    client: &LambdaClient,
+    out: &Output,
+    filter: &InstanceFilter,
+    view: &RunningView,
+    interval: Duration,
+) -> Result<()> {
+    let out = Output {
+        format: if out.is_json() {
+            OutputFormat::JsonCompact
+        } else {
+            OutputFormat::Table
+        },
+    };
 
     loop {
-        if start_time.elapsed() > max_wait {
-            println!(
-                "{} Instance may still be starting. Check status with: lambda running",
-                "Timeout:".yellow()
-            );
-            break;
+        // Each refresh gets its own retry budget
+        let client = client.for_operation();
+        if !out.is_json() {
+            clear_screen();
+        }
+        if let Err(e) = list_running_instances(rt, &client, &out, filter, view) {
+            eprintln!("{} {}", "Warning:".yellow(), e);
         }
+        stdout().flush()?;
+        std::thread::sleep(interval);
+    }
+}
 
-        std::thread::sleep(poll_interval);
+fn list_running_instances(
+    rt: &Runtime,
+    client: &LambdaClient,
+    out: &Output,
+    filter: &InstanceFilter,
+    view: &RunningView,
+) -> Result<()> {
+    let mut instances = rt.block_on(client.list_running_instances())?;
+    instances.retain(|inst| filter.matches(inst));
 
-        match rt.block_on(client.get_instance(&result.instance_id)) {
-            Ok(instance) => {
-                let status = instance.status.as_deref().unwrap_or("unknown");
-                print!(
-                    "\r{} Status: {}    ",
-                    "Polling...".dimmed(),
-                    status.yellow()
-                );
-                stdout().flush().ok();
-
-                // Ready when IP is available (don't wait for "active" status)
-                if let Some(ref ip) = instance.ip {
-                    println!();
-                    println!(
-                        "{} Instance is ready! SSH: {}",
-                        "Ready!".green().bold(),
-                        format!("ssh ubuntu@{}", ip).cyan()
-                    );
+    if view.count_only {
+        if out.is_json() {
+            return out.print_json(&serde_json::json!({
+                "gpu": filter.gpu,
+                "ssh_key": filter.ssh_key,
+                "count": instances.len(),
+            }));
+        }
+        println!("{}", instances.len());
+        return Ok(());
+    }
 
-                    // Send notification if configured
-                    if let Some(ref notifier) = notifier {
-                        let msg = InstanceReadyMessage {
-                            instance_id: result.instance_id.clone(),
-                            instance_name: name.map(String::from),
-                            ip: ip.clone(),
-                            gpu_type: gpu.to_string(),
-                            region: result.region.clone(),
-                        };
+    match view.sort {
+        Some(RunningSort::Cost) => {
+            let types = rt.block_on(client.instance_types_by_name())?;
+            let price = |inst: &Instance| {
+                inst.type_name()
+                    .and_then(|t| types.get(t))
+                    .map_or(0, |t| t.price_cents_per_hour)
+            };
+            instances.sort_by_key(|inst| std::cmp::Reverse(price(inst)));
+        }
+        Some(RunningSort::Name) => instances.sort_by(|a, b| a.name.cmp(&b.name)),
+        Some(RunningSort::Type) => instances.sort_by(|a, b| a.type_name().cmp(&b.type_name())),
+        Some(RunningSort::Region) => instances.sort_by(|a, b| {
+            let region = |i: &Instance| i.region.as_ref().and_then(|r| r.name.clone());
+            region(a).cmp(&region(b))
+        }),
+        Some(RunningSort::Status) => instances.sort_by_key(Instance::status_display),
+        None => {}
+    }
 
-                        println!("{} Sending notifications...", "Info:".blue());
-                        let results = rt.block_on(notifier.send_all(&msg));
-                        for (channel, result) in results {
-                            match result {
-                                Ok(()) => {
-                                    println!("  {} {} notification sent", "✓".green(), channel)
-                                }
-                                Err(e) => println!(
-                                    "  {} {} notification failed: {}",
-                                    "✗".red(),
-                                    channel,
-                                    e
-                                ),
-                            }
-                        }
-                    }
-                    break;
-                } else if status == "terminated" || status == "unhealthy" {
-                    println!();
-                    return Err(anyhow!("Instance entered {} state", status));
-                }
-            }
-            Err(e) => {
-                print!("\r{} Waiting for instance...    ", "Polling...".dimmed());
-                stdout().flush().ok();
-                eprintln!("\nWarning: {}", e);
-            }
+    let total = instances.len();
+    if let Some(limit) = view.limit {
+        instances.truncate(limit);
+    }
+
+    if let Some(template) = &view.template {
+        for instance in &instances {
+            println!("{}", template.render(instance));
         }
+        return Ok(());
     }
 
+    if out.is_json() {
+        return out.print_json(&instances);
+    }
+
+    if instances.is_empty() {
+        println!("{}", "No running instances".yellow());
+        return Ok(());
+    }
+
+    running_table(&instances, &view.columns).printstd();
+    if instances.len() < total {
+        println!("(showing {} of {})", instances.len(), total);
+    }
     Ok(())
 }
 
-fn stop_instance(rt: &Runtime, client: &LambdaClient, instance_id: &str) -> Result<()> {
-    println!("Terminating instance {}...", instance_id.cyan());
+/// `lambda running` table of `instances`
+fn running_table(instances: &[Instance], columns: &[RunningColumn]) -> Table {
+    let columns = columns_or_all(columns);
+    let mut table = new_table(columns.iter().map(|c| Cell::new(c.header())).collect());
+    for instance in instances {
+        table.add_row(
+            columns
+                .iter()
+                .map(|c| Cell::new(&c.cell(instance)))
+                .collect(),
+        );
+    }
+    table
+}
 
-    rt.block_on(client.terminate_instance(instance_id))?;
+/// Status table `find` redraws after each check on a terminal
+fn find_status_table(gpu: &str, check_time: &str, status: &ColoredString) -> Table {
+    let mut table = new_table(row!["Instance Type", "Last Checked", "Status"]);
+    table.add_row(row![gpu.green(), check_time, status]);
+    table
+}
 
-    println!(
-        "{} Instance {} terminated",
-        "Success!".green().bold(),
-        instance_id.cyan()
-    );
-    Ok(())
+/// Symbol for an instance status, distinguishable without color
+fn status_glyph(status: Option<&InstanceStatus>) -> &'static str {
+    match status {
+        Some(InstanceStatus::Active) => "●",
+        Some(InstanceStatus::Booting) => "◐",
+        Some(s) if s.is_failed() => "✗",
+        _ => "○",
+    }
 }
 
-fn list_running_instances(rt: &Runtime, client: &LambdaClient) -> Result<()> {
-    let instances = rt.block_on(client.list_running_instances())?;
+/// Regions `find` may launch in
+struct RegionFilter {
+    /// If non-empty, the only allowed regions
+    only: Vec<String>,
+    exclude: Vec<String>,
+    /// Checks without capacity in an allowed region before any region will do
+    fallback_after: Option<u32>,
+}
 
-    if instances.is_empty() {
-        println!("{}", "No running instances".yellow());
-        return Ok(());
+impl RegionFilter {
+    fn allows(&self, region: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|r| r == region))
+            && !self.exclude.iter().any(|r| r == region)
     }
+}
 
-    let mut table = Table::new();
-    table.add_row(row![
-        "Instance ID",
-        "Name",
-        "Type",
-        "Region",
-        "Status",
-        "IP Address",
-        "SSH Keys"
-    ]);
+/// Where `find` sends each check's availability snapshot
+struct FindSnapshots {
+    /// One JSON line per check, in place of the status line
+    stdout: bool,
+    file: Option<std::path::PathBuf>,
+}
 
-    for instance in instances {
-        let status = instance.status.as_deref().unwrap_or("N/A");
-        let status_colored = match status {
-            "active" => status.green().to_string(),
-            "booting" => status.yellow().to_string(),
-            "unhealthy" | "terminated" => status.red().to_string(),
-            _ => status.to_string(),
+#[derive(Serialize)]
+struct AvailabilitySnapshot<'a> {
+    gpu: &'a str,
+    checked_at: String,
+    regions: Vec<RegionAvailability<'a>>,
+}
+
+#[derive(Serialize)]
+struct RegionAvailability<'a> {
+    name: &'a str,
+    /// Whether --only-regions/--exclude-regions let `find` launch here
+    allowed: bool,
+}
+
+impl FindSnapshots {
+    fn write(&self, gpu: &str, available: &[String], regions: &RegionFilter) -> Result<()> {
+        if !self.stdout && self.file.is_none() {
+            return Ok(());
+        }
+        let snapshot = AvailabilitySnapshot {
+            gpu,
+            checked_at: chrono::Utc::now().to_rfc3339(),
+            regions: available
+                .iter()
+                .map(|name| RegionAvailability {
+                    name,
+                    allowed: regions.allows(name),
+                })
+                .collect(),
         };
+        let json = serde_json::to_string(&snapshot)?;
 
-        table.add_row(row![
-            instance.id.unwrap_or_else(|| "N/A".to_string()).cyan(),
-            instance.name.unwrap_or_else(|| "-".to_string()).white(),
-            instance
-                .instance_type
-                .and_then(|t| t.name)
-                .unwrap_or_else(|| "N/A".to_string()),
-            instance
-                .region
-                .and_then(|r| r.name)
-                .unwrap_or_else(|| "N/A".to_string()),
-            status_colored,
-            instance.ip.unwrap_or_else(|| "N/A".to_string()).blue(),
-            instance
-                .ssh_key_names
-                .map(|keys| keys.join(", "))
-                .unwrap_or_else(|| "N/A".to_string())
-        ]);
+        if self.stdout {
+            println!("{}", json);
+        }
+        if let Some(path) = &self.file {
+            // Write beside the target and rename, so readers never see a partial file
+            let mut tmp = path.clone().into_os_string();
+            tmp.push(".tmp");
+            std::fs::write(&tmp, json + "\n")
+                .and_then(|()| std::fs::rename(&tmp, path))
+                .with_context(|| format!("Failed to write status file {}", path.display()))?;
+        }
+        Ok(())
     }
-
-    table.printstd();
-    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -405,45 +2880,164 @@ fn find_and_start_instance(
     gpu: &str,
     ssh: &str,
     interval: u64,
+    allow_fast_polling: bool,
     name: Option<&str>,
+    timestamp_name: bool,
+    keep_alive: bool,
+    regions: &RegionFilter,
     filesystem: Option<&str>,
     no_notify: bool,
+    snapshots: &FindSnapshots,
+    change_notifier: Option<&Notifier>,
 ) -> Result<()> {
     if ssh.is_empty() {
         return Err(LambdaError::SshKeyRequired.into());
     }
 
-    println!(
+    if snapshots.stdout {
+        PROGRESS_TO_STDERR.store(true, Ordering::Relaxed);
+    }
+
+    let min_interval = POLL_INTERVAL_FLOOR.as_secs();
+    let interval = if interval < min_interval && !allow_fast_polling {
+        eprintln!(
+            "{} --interval {}s is below the {}s minimum; using {}s (pass --allow-fast-polling to override)",
+            "Warning:".yellow(),
+            interval,
+            min_interval,
+            min_interval
+        );
+        min_interval
+    } else {
+        interval
+    };
+
+    progress!(
         "Looking for available {} instances (polling every {}s)...",
         gpu.green(),
         interval
     );
-    println!("Press Ctrl+C to stop\n");
+    progress!("Press Ctrl+C to stop\n");
 
     let mut first_check = true;
+    let mut heartbeat = keep_alive.then(Heartbeat::default);
+    // Redrawing a status table only makes sense on a terminal; logs get one line per check,
+    // which --regions-json sends to stderr along with all other progress
+    let redraw = stdout().is_terminal() && !snapshots.stdout;
+    // Completed checks that found no capacity in an allowed region
+    let mut misses: u32 = 0;
+    // Regions with capacity at the last successful check, sorted, for --notify-on-change
+    let mut last_regions: Option<Vec<String>> = None;
 
     loop {
         if !first_check {
             std::thread::sleep(Duration::from_secs(interval));
         }
         first_check = false;
+        // Each check (and the launches it leads to) gets its own retry budget
+        let client = &client.for_operation();
 
         let check_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
 
-        match rt.block_on(client.check_availability(gpu)) {
-            Ok(regions) if !regions.is_empty() => {
-                execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0)).ok();
-                println!(
-                    "{} Found {} available in: {}",
-                    "SUCCESS!".green().bold(),
-                    gpu.green(),
-                    regions.join(", ").blue()
-                );
+        let mut status = "No availability".red();
+        let result = rt.block_on(client.check_availability(gpu));
+        if let Some(ref mut heartbeat) = heartbeat {
+            heartbeat.record(result.is_ok(), &check_time);
+        }
+        if let Ok(available) = &result {
+            if let Err(e) = snapshots.write(gpu, available, regions) {
+                eprintln!("{} {:#}", "Warning:".yellow(), e);
+            }
+            if let Some(notifier) = change_notifier {
+                let mut now = available.clone();
+                now.sort();
+                // The first check only sets the baseline
+                match last_regions.replace(now.clone()) {
+                    Some(before) if before != now => {
+                        let msg = CapacityChangeMessage {
+                            gpu_type: gpu.to_string(),
+                            before,
+                            after: now,
+                        };
+                        progress!(
+                            "{} {} capacity changed; sending notification...",
+                            "Info:".blue(),
+                            gpu
+                        );
+                        print_notify_results(rt.block_on(notifier.send_text(&msg.plain_text())));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        match result {
+            Ok(available) if !available.is_empty() => {
+                let (allowed, disallowed): (Vec<String>, Vec<String>) =
+                    available.into_iter().partition(|r| regions.allows(r));
+                let allowed =
+                    if allowed.is_empty() && regions.fallback_after.is_some_and(|n| misses >= n) {
+                        eprintln!(
+                        "{} No capacity in the allowed regions after {} checks; falling back to {}",
+                        "WARNING:".red().bold(),
+                        misses,
+                        disallowed.join(", ")
+                    );
+                        disallowed.clone()
+                    } else {
+                        allowed
+                    };
+                if allowed.is_empty() {
+                    misses += 1;
+                    status =
+                        format!("Only in excluded regions: {}", disallowed.join(", ")).yellow();
+                } else {
+                    clear_screen();
+                    progress!(
+                        "{} Found {} available in: {}",
+                        "SUCCESS!".green().bold(),
+                        gpu.green(),
+                        allowed.join(", ").blue()
+                    );
 
-                return start_instance(rt, client, gpu, ssh, name, None, filesystem, no_notify);
+                    let name = name.map(|name| {
+                        if timestamp_name {
+                            format!("{}-{}", name, Local::now().format("%Y%m%d-%H%M%S"))
+                        } else {
+                            name.to_string()
+                        }
+                    });
+                    // Launch where capacity was just seen: an allowed region, unless falling
+                    // back. Others may grab it first, so try each region seen before polling again.
+                    for region in &allowed {
+                        let launch = LaunchOptions {
+                            name: name.clone(),
+                            region: Some(region.clone()),
+                            filesystem: filesystem.map(String::from),
+                            ..Default::default()
+                        };
+                        match start_instance(
+                            rt,
+                            client,
+                            &[gpu],
+                            ssh,
+                            &launch,
+                            no_notify,
+                            DEFAULT_POLL_INTERVAL_SECS,
+                            false,
+                        ) {
+                            Err(e) if is_no_capacity(&e) => {
+                                eprintln!("{} {}", "Capacity gone:".yellow(), e);
+                            }
+                            result => return result,
+                        }
+                    }
+                    misses += 1;
+                    status = "Capacity gone before launch".yellow();
+                }
             }
             Ok(_) => {
                 // No availability
+                misses += 1;
             }
             Err(e) => {
                 if e.to_string().contains("not found") {
@@ -454,29 +3048,102 @@ fn find_and_start_instance(
                     "Warning:".yellow(),
                     e
                 );
+                if let Some(ref heartbeat) = heartbeat {
+                    heartbeat.report();
+                }
                 continue;
             }
         }
 
+        if redraw {
+            clear_screen();
+            find_status_table(gpu, &check_time, &status).printstd();
+            progress!("\nNext check in {} seconds... (Ctrl+C to stop)", interval);
+        } else {
+            progress!("[{}] {}: {}", check_time, gpu, status);
+        }
+        if let Some(ref heartbeat) = heartbeat {
+            heartbeat.report();
+        }
+    }
+}
+
+/// Clear the terminal for a fresh status screen; piped output gets no escape codes
+fn clear_screen() {
+    if stdout().is_terminal() && !PROGRESS_TO_STDERR.load(Ordering::Relaxed) {
         execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0)).ok();
-        let mut table = Table::new();
-        table.add_row(row!["Instance Type", "Last Checked", "Status"]);
-        table.add_row(row![gpu.green(), check_time, "No availability".red()]);
-        table.printstd();
-        println!("\nNext check in {} seconds... (Ctrl+C to stop)", interval);
     }
 }
 
-fn list_filesystems(rt: &Runtime, client: &LambdaClient) -> Result<()> {
-    let filesystems = rt.block_on(client.list_filesystems())?;
+/// Checks between `find --keep-alive` heartbeat lines
+const HEARTBEAT_EVERY_CHECKS: u64 = 6;
+/// Consecutive failed checks before `find --keep-alive` warns about connectivity
+const HEARTBEAT_ERROR_THRESHOLD: u32 = 3;
+
+/// Liveness stats for `find --keep-alive`
+#[derive(Default)]
+struct Heartbeat {
+    checks: u64,
+    last_success: Option<String>,
+    consecutive_errors: u32,
+}
+
+impl Heartbeat {
+    fn record(&mut self, ok: bool, time: &str) {
+        self.checks += 1;
+        if ok {
+            self.last_success = Some(time.to_string());
+            self.consecutive_errors = 0;
+        } else {
+            self.consecutive_errors += 1;
+        }
+    }
+
+    fn report(&self) {
+        if self.consecutive_errors >= HEARTBEAT_ERROR_THRESHOLD {
+            eprintln!(
+                "{} {} consecutive checks failed; the API may be unreachable (last success: {})",
+                "WARNING:".red().bold(),
+                self.consecutive_errors,
+                self.last_success.as_deref().unwrap_or("never")
+            );
+        } else if self.checks.is_multiple_of(HEARTBEAT_EVERY_CHECKS) {
+            progress!(
+                "{} {} checks, last successful poll {}, {} consecutive errors",
+                "Heartbeat:".dimmed(),
+                self.checks,
+                self.last_success.as_deref().unwrap_or("never"),
+                self.consecutive_errors
+            );
+        }
+    }
+}
+
+fn list_filesystems(
+    rt: &Runtime,
+    client: &LambdaClient,
+    out: &Output,
+    region: Option<&str>,
+) -> Result<()> {
+    let mut filesystems = rt.block_on(client.list_filesystems())?;
+    if let Some(region) = region {
+        let region = normalize_region(region);
+        filesystems.retain(|fs| fs.region.name == region);
+    }
+
+    if out.is_json() {
+        return out.print_json(&filesystems);
+    }
 
     if filesystems.is_empty() {
-        println!("{}", "No filesystems".yellow());
+        match region {
+            Some(region) => println!("{}", format!("No filesystems in {}", region).yellow()),
+            None => println!("{}", "No filesystems".yellow()),
+        }
         return Ok(());
     }
 
-    let mut table = Table::new();
-    table.add_row(row![
+    let mut table = new_table(row![
         "ID",
         "Name",
         "Region",
@@ -493,15 +3160,7 @@ fn list_filesystems(rt: &Runtime, client: &LambdaClient) -> Result<()> {
             "No".dimmed().to_string()
         };
 
-        let bytes_str = if fs.bytes_used > 1_000_000_000 {
-            format!("{:.2} GB", fs.bytes_used as f64 / 1_000_000_000.0)
-        } else if fs.bytes_used > 1_000_000 {
-            format!("{:.2} MB", fs.bytes_used as f64 / 1_000_000.0)
-        } else if fs.bytes_used > 1_000 {
-            format!("{:.2} KB", fs.bytes_used as f64 / 1_000.0)
-        } else {
-            format!("{} B", fs.bytes_used)
-        };
+        let bytes_str = format_bytes(fs.bytes_used);
 
         table.add_row(row![
             fs.id.cyan(),
@@ -518,6 +3177,207 @@ fn list_filesystems(rt: &Runtime, client: &LambdaClient) -> Result<()> {
     Ok(())
 }
 
+/// Ask before launching any of `gpus` that costs more than `threshold` $/hr
+fn confirm_launch_price(
+    rt: &Runtime,
+    client: &LambdaClient,
+    gpus: &[&str],
+    threshold: f64,
+) -> Result<bool> {
+    let types = rt.block_on(client.instance_types_by_name())?;
+    let expensive: Vec<(&str, f64)> = gpus
+        .iter()
+        .filter_map(|gpu| {
+            let dollars = types.get(*gpu)?.price_cents_per_hour as f64 / 100.0;
+            (dollars > threshold).then_some((*gpu, dollars))
+        })
+        .collect();
+    if expensive.is_empty() {
+        return Ok(true);
+    }
+
+    require_terminal(&format!("launch above ${:.2}/hr", threshold), SKIP_PROMPT)?;
+    for (gpu, dollars) in &expensive {
+        println!(
+            "{} costs {}/hr ({}/day), above --confirm-price ${:.2}/hr.",
+            gpu.green(),
+            format!("${:.2}", dollars).yellow(),
+            format!("${:.2}", dollars * HOURS_PER_DAY).yellow(),
+            threshold
+        );
+    }
+    ask_yes_no()
+}
+
+/// Ask on the terminal before deleting a filesystem
+fn confirm_delete_filesystem(fs: &Filesystem) -> Result<bool> {
+    let action = format!("delete filesystem {}", fs.id);
+    require_terminal(&action, SKIP_PROMPT)?;
+
+    println!(
+        "This will permanently delete filesystem {} ({}) in {}, holding {}.",
+        fs.name.green(),
+        fs.id.cyan(),
+        fs.region.name,
+        format_bytes(fs.bytes_used)
+    );
+    ask_yes_no()
+}
+
+/// Use the given instance ID, or let the user pick a running instance in a terminal
+fn instance_id_or_pick(
+    rt: &Runtime,
+    client: &LambdaClient,
+    instance_id: Option<&str>,
+    action: &str,
+) -> Result<String> {
+    if let Some(id) = instance_id {
+        return Ok(id.to_string());
+    }
+    require_terminal(
+        &format!("pick an instance to {}", action),
+        "pass --instance-id",
+    )?;
+
+    let instances = rt.block_on(client.list_running_instances())?;
+    if instances.is_empty() {
+        anyhow::bail!("No running instances to {}", action);
+    }
+
+    println!("Select an instance to {}:", action);
+    for (i, instance) in instances.iter().enumerate() {
+        println!(
+            "  {}) {} {} ({}, {})",
+            i + 1,
+            instance.id.as_deref().unwrap_or("N/A").cyan(),
+            instance.name.as_deref().unwrap_or("-"),
+            instance.type_name().unwrap_or("N/A"),
+            instance.status_display()
+        );
+    }
+    print!("Number [1-{}]: ", instances.len());
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    let choice = answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| instances.get(i))
+        .ok_or_else(|| anyhow!("Invalid selection '{}'", answer.trim()))?;
+    choice
+        .id
+        .clone()
+        .ok_or_else(|| anyhow!("Selected instance has no ID"))
+}
+
+/// How to get past a confirmation prompt without a terminal
+const SKIP_PROMPT: &str = "pass --yes to skip the prompt";
+
+/// Refuse (rather than guess an answer) when a prompt can't be answered
+/// interactively; `instead` says which flag answers it up front
+fn require_terminal(action: &str, instead: &str) -> Result<()> {
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "Refusing to {} without a terminal to prompt on; {}",
+            action,
+            instead
+        );
+    }
+    Ok(())
+}
+
+fn ask_yes_no() -> Result<bool> {
+    print!("Continue? [y/N] ");
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes > 1_000_000_000 {
+        format!("{:.2} GB", bytes as f64 / 1_000_000_000.0)
+    } else if bytes > 1_000_000 {
+        format!("{:.2} MB", bytes as f64 / 1_000_000.0)
+    } else if bytes > 1_000 {
+        format!("{:.2} KB", bytes as f64 / 1_000.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+fn filesystem_status(
+    rt: &Runtime,
+    client: &LambdaClient,
+    out: &Output,
+    filesystem_id: &str,
+) -> Result<()> {
+    let (fs, instances) = rt.block_on(async {
+        tokio::try_join!(
+            client.get_filesystem(filesystem_id),
+            client.list_running_instances()
+        )
+    })?;
+
+    let used_by: Vec<&Instance> = instances
+        .iter()
+        .filter(|inst| {
+            inst.file_system_names
+                .as_ref()
+                .is_some_and(|names| names.contains(&fs.name))
+        })
+        .collect();
+
+    if out.is_json() {
+        return out.print_json(&serde_json::json!({
+            "filesystem": fs,
+            "used_by": used_by,
+        }));
+    }
+
+    println!("Filesystem {} ({})", fs.name.green(), fs.id.cyan());
+    println!(
+        "  Region: {} ({})",
+        fs.region.name.blue(),
+        fs.region.description
+    );
+    println!("  Mount point: {}", fs.mount_point);
+    println!("  Used: {}", format_bytes(fs.bytes_used));
+    println!(
+        "  In use: {}",
+        if fs.is_in_use {
+            "Yes".green().to_string()
+        } else {
+            "No".dimmed().to_string()
+        }
+    );
+
+    if used_by.is_empty() {
+        if fs.is_in_use {
+            println!(
+                "  Attached to: {}",
+                "unknown (not found on running instances)".dimmed()
+            );
+        }
+    } else {
+        println!("  Attached to:");
+        for inst in used_by {
+            println!(
+                "    {} {} ({})",
+                inst.id.as_deref().unwrap_or("N/A").cyan(),
+                inst.name.as_deref().unwrap_or("-"),
+                inst.status_display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
 fn create_filesystem(rt: &Runtime, client: &LambdaClient, name: &str, region: &str) -> Result<()> {
     println!(
         "Creating filesystem '{}' in region {}...",
@@ -539,7 +3399,20 @@ fn create_filesystem(rt: &Runtime, client: &LambdaClient, name: &str, region: &s
     Ok(())
 }
 
-fn delete_filesystem(rt: &Runtime, client: &LambdaClient, filesystem_id: &str) -> Result<()> {
+fn delete_filesystem(
+    rt: &Runtime,
+    client: &LambdaClient,
+    filesystem_id: &str,
+    yes: bool,
+) -> Result<()> {
+    if !yes {
+        let fs = rt.block_on(client.get_filesystem(filesystem_id))?;
+        if !confirm_delete_filesystem(&fs)? {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
     println!("Deleting filesystem {}...", filesystem_id.cyan());
 
     rt.block_on(client.delete_filesystem(filesystem_id))?;
@@ -551,3 +3424,187 @@ fn delete_filesystem(rt: &Runtime, client: &LambdaClient, filesystem_id: &str) -
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_json() {
+        let api = anyhow::Error::from(LambdaError::Unauthorized).context("Pre-flight check failed");
+        assert_eq!(
+            error_json(&api),
+            serde_json::json!({"error": {
+                "code": 401,
+                "message": "Pre-flight check failed",
+                "kind": "unauthorized",
+            }})
+        );
+
+        let request = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let network = error_json(&anyhow::Error::from(request));
+        assert_eq!(network["error"]["kind"], "network");
+        assert_eq!(network["error"]["code"], serde_json::Value::Null);
+        assert!(network["error"]["message"].is_string());
+
+        assert_eq!(
+            error_json(&anyhow!("Invalid selection '9'")),
+            serde_json::json!({"error": {
+                "code": null,
+                "message": "Invalid selection '9'",
+                "kind": "other",
+            }})
+        );
+    }
+
+    #[test]
+    fn test_positional_args() {
+        let parse = |args: &[&str]| -> Result<(String, String), clap::Error> {
+            let mut cli = Cli::try_parse_from(args)?;
+            take_positional_args(&mut cli.command);
+            match cli.command {
+                Some(Commands::Start { gpu, ssh, .. } | Commands::Find { gpu, ssh, .. }) => {
+                    Ok((gpu.unwrap(), ssh.unwrap()))
+                }
+                _ => unreachable!(),
+            }
+        };
+        let pair = |gpu: &str, ssh: &str| (gpu.to_string(), ssh.to_string());
+
+        assert_eq!(
+            parse(&["lambda", "start", "g", "k"]).unwrap(),
+            pair("g", "k")
+        );
+        assert_eq!(
+            parse(&["lambda", "find", "--gpu", "g", "k"]).unwrap(),
+            pair("g", "k")
+        );
+        assert_eq!(
+            parse(&["lambda", "start", "--ssh", "k", "g"]).unwrap(),
+            pair("g", "k")
+        );
+        assert_eq!(
+            parse(&["lambda", "start", "--gpu", "g", "--ssh", "k"]).unwrap(),
+            pair("g", "k")
+        );
+        for missing in [
+            &["lambda", "start"][..],
+            &["lambda", "start", "g"],
+            &["lambda", "find", "--gpu", "g"],
+            &["lambda", "find", "--ssh", "k"],
+        ] {
+            let err = parse(missing).unwrap_err();
+            assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+        }
+    }
+
+    #[test]
+    fn test_parse_launch_time() {
+        let expected = chrono::DateTime::from_timestamp(1_735_732_800, 0).unwrap();
+        assert_eq!(parse_launch_time("2025-01-01T12:00:00Z").unwrap(), expected);
+        assert_eq!(
+            parse_launch_time("2025-01-01T13:00:00+01:00").unwrap(),
+            expected
+        );
+        assert_eq!(parse_launch_time("1735732800").unwrap(), expected);
+
+        assert_eq!(
+            parse_launch_time(&i64::MAX.to_string()).unwrap_err(),
+            format!("'{}' is out of range", i64::MAX)
+        );
+        assert!(parse_launch_time("2025-01-01")
+            .unwrap_err()
+            .starts_with("expected an RFC 3339 time"));
+        assert!(parse_launch_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_remaining_timeout() {
+        let now = chrono::Utc::now();
+        let remaining = remaining_timeout(now - chrono::Duration::seconds(100), 300).unwrap();
+        // Allow for the clock ticking between `now` and the call
+        assert!((199..=200).contains(&remaining), "{}", remaining);
+
+        let err = remaining_timeout(now - chrono::Duration::seconds(400), 300).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("The 300s timeout already ran out"));
+        assert!(remaining_timeout(now - chrono::Duration::seconds(300), 300).is_err());
+
+        // A launch time ahead of this clock counts as just launched
+        let skewed = remaining_timeout(now + chrono::Duration::seconds(60), 300).unwrap();
+        assert_eq!(skewed, 300);
+    }
+
+    #[test]
+    fn test_terminate_on_interrupt_unwinds() {
+        let rt = Runtime::new().unwrap();
+        let client = LambdaClient::new("test-key".to_string()).unwrap();
+        assert_eq!(terminate_on_interrupt(&rt, &client, "i-1", || 7), 7);
+
+        // A panicking body must unwind rather than wait forever on the watcher
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            terminate_on_interrupt(&rt, &client, "i-1", || panic!("body failed"))
+        }));
+        assert!(result.is_err());
+    }
+
+    // One test, since the color override is process-wide
+    #[test]
+    fn test_no_color_has_no_escapes() {
+        let types = vec![
+            InstanceTypeData {
+                name: "gpu_1x_a10".to_string(),
+                description: "1x A10 (24 GB PCIe)".to_string(),
+                price_cents_per_hour: 75,
+                vcpus: 30,
+                memory_gib: 200,
+                storage_gib: 1400,
+                regions_available: vec!["us-east-1".to_string()],
+            },
+            InstanceTypeData {
+                name: "gpu_8x_h100_sxm5".to_string(),
+                description: "8x H100 (80 GB SXM5)".to_string(),
+                price_cents_per_hour: 2392,
+                vcpus: 208,
+                memory_gib: 1800,
+                storage_gib: 24780,
+                regions_available: vec![],
+            },
+        ];
+        let instances: Vec<Instance> = serde_json::from_value(serde_json::json!([
+            {"id": "i-1", "name": "train", "status": "active", "ip": "10.0.0.1"},
+            {"id": "i-2", "status": "booting"},
+            {"id": "i-3", "status": "unhealthy"},
+        ]))
+        .unwrap();
+        let render = || {
+            [
+                instance_types_table(&types, &[], "Price", |cents| cents.to_string()).to_string(),
+                running_table(&instances, &[]).to_string(),
+                find_status_table("gpu_1x_a10", "2025-01-01 12:00:00", &"Available!".green())
+                    .to_string(),
+                format!("{} Instance launched", "Success!".green().bold()),
+            ]
+            .concat()
+        };
+
+        apply_color(&Cli::try_parse_from(["lambda", "--color", "always"]).unwrap());
+        assert!(render().contains("\x1b["));
+
+        for args in [
+            &["lambda", "--no-color"][..],
+            &["lambda", "--color", "never"],
+        ] {
+            apply_color(&Cli::try_parse_from(args).unwrap());
+            let output = render();
+            assert!(
+                !output.contains("\x1b["),
+                "{:?} left escapes in {}",
+                args,
+                output
+            );
+            assert!(output.contains("● active"));
+        }
+    }
+}
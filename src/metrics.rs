@@ -0,0 +1,138 @@
+//! Prometheus text exposition of fleet state, for `lambda serve-metrics`.
+//!
+//! Each scrape refreshes a [`Snapshot`]; if the API can't be reached, the last
+//! good snapshot is served again with `lambda_api_up 0`.
+
+use crate::api::{Instance, InstanceTypeData};
+use crate::cost::estimate_fleet_cost;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// Fleet state as of the last successful refresh
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub instances: Vec<Instance>,
+    /// Instance types by name, for prices
+    pub types: HashMap<String, InstanceTypeData>,
+    /// Unix time of the refresh, or `None` if there hasn't been one
+    pub refreshed_at: Option<i64>,
+}
+
+/// Render `snapshot` in the Prometheus text format; `api_up` is whether this scrape's refresh worked
+pub fn render(snapshot: &Snapshot, api_up: bool) -> String {
+    let mut out = String::new();
+    gauge(
+        &mut out,
+        "lambda_api_up",
+        "Whether the last refresh from the Lambda API succeeded",
+    );
+    writeln!(out, "lambda_api_up {}", u8::from(api_up)).ok();
+
+    if let Some(refreshed_at) = snapshot.refreshed_at {
+        gauge(
+            &mut out,
+            "lambda_last_refresh_timestamp_seconds",
+            "Unix time of the last successful refresh",
+        );
+        writeln!(
+            out,
+            "lambda_last_refresh_timestamp_seconds {}",
+            refreshed_at
+        )
+        .ok();
+    }
+
+    // Count instances per label set; BTreeMap keeps the output stable between scrapes
+    let mut counts: BTreeMap<(&str, &str, &str), u32> = BTreeMap::new();
+    for instance in &snapshot.instances {
+        let labels = (
+            instance.type_name().unwrap_or("unknown"),
+            instance
+                .region
+                .as_ref()
+                .and_then(|r| r.name.as_deref())
+                .unwrap_or("unknown"),
+            instance.status.as_ref().map_or("unknown", |s| s.as_str()),
+        );
+        *counts.entry(labels).or_default() += 1;
+    }
+    gauge(
+        &mut out,
+        "lambda_instances",
+        "Running instances by type, region, and status",
+    );
+    for ((instance_type, region, status), count) in &counts {
+        writeln!(
+            out,
+            "lambda_instances{{instance_type=\"{}\",region=\"{}\",status=\"{}\"}} {}",
+            escape(instance_type),
+            escape(region),
+            escape(status),
+            count
+        )
+        .ok();
+    }
+
+    let cost = estimate_fleet_cost(&snapshot.instances, &snapshot.types);
+    gauge(
+        &mut out,
+        "lambda_fleet_cost_dollars_per_hour",
+        "Estimated hourly spend across running instances",
+    );
+    // abs() because an empty sum is -0.0, which would render as "-0"
+    writeln!(
+        out,
+        "lambda_fleet_cost_dollars_per_hour {}",
+        cost.hourly.abs()
+    )
+    .ok();
+    out
+}
+
+fn gauge(out: &mut String, name: &str, help: &str) {
+    writeln!(out, "# HELP {} {}", name, help).ok();
+    writeln!(out, "# TYPE {} gauge", name).ok();
+}
+
+/// Escape a label value (backslash, quote, and newline)
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let instance: Instance = serde_json::from_value(serde_json::json!({
+            "id": "i-1",
+            "status": "active",
+            "instance_type": {"name": "gpu_1x_a10"},
+            "region": {"name": "us-east-1"},
+        }))
+        .unwrap();
+        let snapshot = Snapshot {
+            instances: vec![instance.clone(), instance],
+            types: HashMap::new(),
+            refreshed_at: Some(1_700_000_000),
+        };
+
+        let text = render(&snapshot, false);
+        assert!(text.contains("lambda_api_up 0\n"));
+        assert!(text.contains("lambda_last_refresh_timestamp_seconds 1700000000\n"));
+        assert!(text.contains(
+            "lambda_instances{instance_type=\"gpu_1x_a10\",region=\"us-east-1\",status=\"active\"} 2\n"
+        ));
+        assert!(text.contains("# TYPE lambda_fleet_cost_dollars_per_hour gauge\n"));
+
+        let empty = render(&Snapshot::default(), true);
+        assert!(empty.contains("lambda_api_up 1\n"));
+        assert!(empty.contains("lambda_fleet_cost_dollars_per_hour 0\n"));
+        assert!(!empty.contains("lambda_last_refresh_timestamp_seconds"));
+        assert_eq!(escape("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
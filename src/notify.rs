@@ -5,12 +5,43 @@
 //! - `LAMBDA_NOTIFY_DISCORD_WEBHOOK` - Discord webhook URL
 //! - `LAMBDA_NOTIFY_TELEGRAM_BOT_TOKEN` - Telegram bot token
 //! - `LAMBDA_NOTIFY_TELEGRAM_CHAT_ID` - Telegram chat ID
+//! - `LAMBDA_NOTIFY_PLAIN` - Set to `1` to send Slack/Discord messages as plain text
 
 use anyhow::Result;
 use reqwest::Client;
+use serde::Serialize;
 use serde_json::json;
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
 use std::time::Duration;
 
+/// Instance IDs whose ready notification this process has delivered or is sending
+static NOTIFIED_INSTANCES: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+/// Claim the ready notification for `instance_id` before sending it.
+///
+/// Returns `false` if it was already delivered or is being sent in this process.
+fn mark_notified(instance_id: &str) -> bool {
+    let set = NOTIFIED_INSTANCES.get_or_init(|| Mutex::new(HashSet::new()));
+    match set.lock() {
+        Ok(mut set) => set.insert(instance_id.to_string()),
+        // A poisoned set only means another sender panicked; don't block notifications
+        Err(_) => true,
+    }
+}
+
+/// Give up a claim from [`mark_notified`] after no channel delivered, so a later send can retry
+fn unmark_notified(instance_id: &str) {
+    if let Some(Ok(mut set)) = NOTIFIED_INSTANCES.get().map(Mutex::lock) {
+        set.remove(instance_id);
+    }
+}
+
+/// Whether at least one channel delivered the message
+fn any_delivered(results: &[(&str, Result<()>)]) -> bool {
+    results.iter().any(|(_, result)| result.is_ok())
+}
+
 /// Message payload for instance ready notifications
 #[derive(Debug, Clone)]
 pub struct InstanceReadyMessage {
@@ -19,16 +50,237 @@ pub struct InstanceReadyMessage {
     pub ip: String,
     pub gpu_type: String,
     pub region: String,
+    /// Human-readable region name, shown instead of the region code when known
+    pub region_description: Option<String>,
+    /// Attached filesystems (shown in detailed notifications)
+    pub filesystems: Vec<String>,
+    /// Time from launch to ready, if measured (shown in detailed notifications)
+    pub ready_after: Option<Duration>,
+}
+
+/// How much a ready notification includes (`LAMBDA_NOTIFY_VERBOSITY`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Name and SSH command only
+    Minimal,
+    /// Name, GPU, region, IP, and SSH command
+    #[default]
+    Normal,
+    /// Everything in `Normal`, plus instance ID, region code, filesystems, and boot time
+    Detailed,
+}
+
+impl Verbosity {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "minimal" => Some(Self::Minimal),
+            "normal" => Some(Self::Normal),
+            "detailed" => Some(Self::Detailed),
+            _ => None,
+        }
+    }
 }
 
 impl InstanceReadyMessage {
     pub fn ssh_command(&self) -> String {
-        format!("ssh ubuntu@{}", self.ip)
+        crate::api::ssh_command(&self.ip)
     }
 
     pub fn display_name(&self) -> &str {
         self.instance_name.as_deref().unwrap_or(&self.instance_id)
     }
+
+    /// Region description if known, otherwise the region code
+    pub fn region_display(&self) -> &str {
+        self.region_description.as_deref().unwrap_or(&self.region)
+    }
+
+    /// Message body without markup, for webhooks that don't render rich payloads
+    pub fn plain_text(&self) -> String {
+        format!(
+            "GPU Instance Ready! {} ({}, {}) at {}. SSH: {}",
+            self.display_name(),
+            self.gpu_type,
+            self.region_display(),
+            self.ip,
+            self.ssh_command()
+        )
+    }
+
+    /// [`plain_text`](Self::plain_text) trimmed or extended to `verbosity`
+    pub fn plain_text_for(&self, verbosity: Verbosity) -> String {
+        match verbosity {
+            Verbosity::Minimal => format!(
+                "GPU Instance Ready! {}. SSH: {}",
+                self.display_name(),
+                self.ssh_command()
+            ),
+            Verbosity::Normal => self.plain_text(),
+            Verbosity::Detailed => {
+                let extra: Vec<String> = self
+                    .detail_fields()
+                    .into_iter()
+                    .map(|(label, value)| format!("{}: {}", label, value))
+                    .collect();
+                format!("{} ({})", self.plain_text(), extra.join(", "))
+            }
+        }
+    }
+
+    /// Labelled fields for rich payloads, in display order; the SSH command is separate
+    pub fn fields(&self, verbosity: Verbosity) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("Name", self.display_name().to_string())];
+        if verbosity == Verbosity::Minimal {
+            return fields;
+        }
+        fields.extend([
+            ("GPU", self.gpu_type.clone()),
+            ("Region", self.region_display().to_string()),
+            ("IP", self.ip.clone()),
+        ]);
+        if verbosity == Verbosity::Detailed {
+            fields.extend(self.detail_fields());
+        }
+        fields
+    }
+
+    /// Fields only [`Verbosity::Detailed`] adds to the normal ones
+    fn detail_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("Instance ID", self.instance_id.clone())];
+        if self.region_description.is_some() {
+            fields.push(("Region Code", self.region.clone()));
+        }
+        if !self.filesystems.is_empty() {
+            fields.push(("Filesystems", self.filesystems.join(", ")));
+        }
+        if let Some(elapsed) = self.ready_after {
+            fields.push(("Ready In", crate::api::format_elapsed(elapsed)));
+        }
+        fields
+    }
+}
+
+/// One summary for a batch of launches, instead of a message per instance
+#[derive(Debug, Clone, Default)]
+pub struct BatchReadyMessage {
+    /// Instances in the batch that are ready
+    pub ready: Vec<InstanceReadyMessage>,
+    /// IDs of instances in the batch that weren't ready when it was reported
+    pub pending: Vec<String>,
+}
+
+impl BatchReadyMessage {
+    pub fn plain_text(&self) -> String {
+        let total = self.ready.len() + self.pending.len();
+        let mut text = if self.pending.is_empty() {
+            format!("All {} GPU instances ready!", total)
+        } else {
+            format!("{} of {} GPU instances ready.", self.ready.len(), total)
+        };
+        for msg in &self.ready {
+            text.push_str(&format!(
+                "\n- {} ({}, {}): {}",
+                msg.display_name(),
+                msg.gpu_type,
+                msg.region_display(),
+                msg.ssh_command()
+            ));
+        }
+        if !self.pending.is_empty() {
+            text.push_str(&format!("\nNot ready: {}", self.pending.join(", ")));
+        }
+        text
+    }
+}
+
+/// The regions with capacity for a GPU type changed, for `find --notify-on-change`
+#[derive(Debug, Clone, Default)]
+pub struct CapacityChangeMessage {
+    pub gpu_type: String,
+    /// Regions with capacity at the previous check
+    pub before: Vec<String>,
+    /// Regions with capacity now
+    pub after: Vec<String>,
+}
+
+impl CapacityChangeMessage {
+    pub fn plain_text(&self) -> String {
+        let list = |regions: &[String]| match regions {
+            [] => "none".to_string(),
+            _ => regions.join(", "),
+        };
+        let appeared: Vec<String> = self
+            .after
+            .iter()
+            .filter(|r| !self.before.contains(r))
+            .cloned()
+            .collect();
+        let gone: Vec<String> = self
+            .before
+            .iter()
+            .filter(|r| !self.after.contains(r))
+            .cloned()
+            .collect();
+
+        let mut text = format!(
+            "{} capacity changed.\nNow: {}\nWas: {}",
+            self.gpu_type,
+            list(&self.after),
+            list(&self.before)
+        );
+        if !appeared.is_empty() {
+            text.push_str(&format!("\nAppeared: {}", appeared.join(", ")));
+        }
+        if !gone.is_empty() {
+            text.push_str(&format!("\nGone: {}", gone.join(", ")));
+        }
+        text
+    }
+}
+
+/// Collects ready messages for a set of instances so they can be sent as one summary
+#[derive(Debug, Clone)]
+pub struct BatchTracker {
+    expected: Vec<String>,
+    ready: Vec<InstanceReadyMessage>,
+}
+
+impl BatchTracker {
+    /// Track the instances with these IDs
+    pub fn new(instance_ids: Vec<String>) -> Self {
+        Self {
+            expected: instance_ids,
+            ready: Vec::new(),
+        }
+    }
+
+    /// Record a ready instance; returns `true` once every tracked instance is ready
+    pub fn record_ready(&mut self, msg: InstanceReadyMessage) -> bool {
+        if self.expected.contains(&msg.instance_id)
+            && !self.ready.iter().any(|m| m.instance_id == msg.instance_id)
+        {
+            self.ready.push(msg);
+        }
+        self.is_complete()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.ready.len() == self.expected.len()
+    }
+
+    /// The summary so far, listing tracked instances that aren't ready yet
+    pub fn message(&self) -> BatchReadyMessage {
+        let pending = self
+            .expected
+            .iter()
+            .filter(|id| !self.ready.iter().any(|m| &m.instance_id == *id))
+            .cloned()
+            .collect();
+        BatchReadyMessage {
+            ready: self.ready.clone(),
+            pending,
+        }
+    }
 }
 
 /// Slack webhook configuration
@@ -56,6 +308,10 @@ pub struct NotifyConfig {
     pub slack: Option<SlackConfig>,
     pub discord: Option<DiscordConfig>,
     pub telegram: Option<TelegramConfig>,
+    /// Send Slack/Discord messages as plain text instead of blocks/embeds
+    pub plain: bool,
+    /// How much each ready message includes
+    pub verbosity: Verbosity,
 }
 
 impl NotifyConfig {
@@ -81,11 +337,25 @@ impl NotifyConfig {
             _ => None,
         };
 
+        let plain = std::env::var("LAMBDA_NOTIFY_PLAIN").is_ok_and(|v| v == "1" || v == "true");
+        let verbosity = match std::env::var("LAMBDA_NOTIFY_VERBOSITY") {
+            Ok(value) if !value.is_empty() => Verbosity::parse(&value).unwrap_or_else(|| {
+                eprintln!(
+                    "Warning: ignoring LAMBDA_NOTIFY_VERBOSITY '{}' (expected minimal, normal, or detailed)",
+                    value
+                );
+                Verbosity::Normal
+            }),
+            _ => Verbosity::Normal,
+        };
+
         if slack.is_some() || discord.is_some() || telegram.is_some() {
             Some(Self {
                 slack,
                 discord,
                 telegram,
+                plain,
+                verbosity,
             })
         } else {
             None
@@ -113,6 +383,60 @@ impl NotifyConfig {
     }
 }
 
+/// Whether one channel would fire, with a summary of its settings that leaves out secrets
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelStatus {
+    pub channel: &'static str,
+    pub configured: bool,
+    pub detail: String,
+}
+
+impl ChannelStatus {
+    /// Status of every channel as configured in the environment
+    pub fn from_env() -> Vec<Self> {
+        Self::from_vars(|name| std::env::var(name).ok().filter(|v| !v.is_empty()))
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Vec<Self> {
+        let webhook = |channel, name: &str| match var(name) {
+            Some(url) => Self {
+                channel,
+                configured: true,
+                detail: match reqwest::Url::parse(&url) {
+                    Ok(url) => format!("webhook host {}", url.host_str().unwrap_or("(none)")),
+                    Err(_) => "webhook URL is not a valid URL".to_string(),
+                },
+            },
+            None => Self {
+                channel,
+                configured: false,
+                detail: format!("{} not set", name),
+            },
+        };
+
+        let set = |name| {
+            if var(name).is_some() {
+                "set"
+            } else {
+                "missing"
+            }
+        };
+        let token = set("LAMBDA_NOTIFY_TELEGRAM_BOT_TOKEN");
+        let chat_id = set("LAMBDA_NOTIFY_TELEGRAM_CHAT_ID");
+        let telegram = Self {
+            channel: "Telegram",
+            configured: token == "set" && chat_id == "set",
+            detail: format!("bot token {}, chat ID {}", token, chat_id),
+        };
+
+        vec![
+            webhook("Slack", "LAMBDA_NOTIFY_SLACK_WEBHOOK"),
+            webhook("Discord", "LAMBDA_NOTIFY_DISCORD_WEBHOOK"),
+            telegram,
+        ]
+    }
+}
+
 /// Notifier for sending messages to configured channels
 pub struct Notifier {
     client: Client,
@@ -130,6 +454,14 @@ impl Notifier {
         Self { client, config }
     }
 
+    /// Send notifications through `client`, sharing its connection pool.
+    ///
+    /// The injected client's timeouts apply instead of the notifier's 10s default.
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
     /// Create a notifier from environment variables, if configured
     pub fn from_env() -> Option<Self> {
         NotifyConfig::from_env().map(Self::new)
@@ -137,10 +469,29 @@ impl Notifier {
 
     /// Send notification to all configured channels
     ///
+    /// Once a channel has delivered an instance's ready message, repeated calls
+    /// for the same instance ID in this process return an empty list. If every
+    /// channel failed, a later call tries again.
+    ///
     /// Returns a list of (channel_name, result) for each attempt
     pub async fn send_all(&self, msg: &InstanceReadyMessage) -> Vec<(&'static str, Result<()>)> {
+        self.send_all_with_options(msg, false).await
+    }
+
+    /// Send notification to all configured channels with options.
+    ///
+    /// If `force` is true, the per-instance deduplication is bypassed (e.g. for test messages).
+    pub async fn send_all_with_options(
+        &self,
+        msg: &InstanceReadyMessage,
+        force: bool,
+    ) -> Vec<(&'static str, Result<()>)> {
         let mut results = Vec::new();
 
+        if !force && !mark_notified(&msg.instance_id) {
+            return results;
+        }
+
         if let Some(ref slack) = self.config.slack {
             let result = self.send_slack(slack, msg).await;
             results.push(("Slack", result));
@@ -156,12 +507,114 @@ impl Notifier {
             results.push(("Telegram", result));
         }
 
+        if !force && !any_delivered(&results) {
+            unmark_notified(&msg.instance_id);
+        }
         results
     }
 
+    /// Send one summary for a batch to all configured channels, as plain text.
+    ///
+    /// Instances in the summary count as notified, so they won't also get
+    /// individual ready messages from `send_all`, unless no channel delivers it.
+    pub async fn send_batch(&self, batch: &BatchReadyMessage) -> Vec<(&'static str, Result<()>)> {
+        let claimed: Vec<&str> = batch
+            .ready
+            .iter()
+            .map(|msg| msg.instance_id.as_str())
+            .filter(|id| mark_notified(id))
+            .collect();
+        let results = self.send_text(&batch.plain_text()).await;
+        if !any_delivered(&results) {
+            claimed.into_iter().for_each(unmark_notified);
+        }
+        results
+    }
+
+    /// Send a plain-text message to all configured channels
+    pub async fn send_text(&self, text: &str) -> Vec<(&'static str, Result<()>)> {
+        let mut results = Vec::new();
+
+        if let Some(ref slack) = self.config.slack {
+            let result = self
+                .post(
+                    &slack.webhook_url,
+                    &json!({ "text": text }),
+                    "Slack webhook",
+                )
+                .await;
+            results.push(("Slack", result));
+        }
+
+        if let Some(ref discord) = self.config.discord {
+            let result = self
+                .post(
+                    &discord.webhook_url,
+                    &json!({ "content": text }),
+                    "Discord webhook",
+                )
+                .await;
+            results.push(("Discord", result));
+        }
+
+        if let Some(ref telegram) = self.config.telegram {
+            let url = format!(
+                "https://api.telegram.org/bot{}/sendMessage",
+                telegram.bot_token
+            );
+            let payload = json!({ "chat_id": telegram.chat_id, "text": text });
+            results.push(("Telegram", self.post(&url, &payload, "Telegram API").await));
+        }
+
+        results
+    }
+
+    /// POST a JSON payload, treating any non-success status as an error
+    async fn post(&self, url: &str, payload: &serde_json::Value, what: &str) -> Result<()> {
+        let response = self.client.post(url).json(payload).send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("{} failed ({}): {}", what, status, body);
+        }
+
+        Ok(())
+    }
+
     /// Send notification to Slack
     async fn send_slack(&self, config: &SlackConfig, msg: &InstanceReadyMessage) -> Result<()> {
-        let payload = json!({
+        let verbosity = self.config.verbosity;
+        let payload = if self.config.plain {
+            json!({ "text": msg.plain_text_for(verbosity) })
+        } else {
+            Self::slack_blocks(msg, verbosity)
+        };
+
+        let response = self
+            .client
+            .post(&config.webhook_url)
+            .json(&payload)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Slack webhook failed ({}): {}", status, body);
+        }
+
+        Ok(())
+    }
+
+    fn slack_blocks(msg: &InstanceReadyMessage, verbosity: Verbosity) -> serde_json::Value {
+        let fields: Vec<serde_json::Value> = msg
+            .fields(verbosity)
+            .into_iter()
+            .map(|(label, value)| json!({ "type": "mrkdwn", "text": format!("*{}:*\n{}", label, value) }))
+            .collect();
+
+        json!({
             "blocks": [
                 {
                     "type": "header",
@@ -173,24 +626,7 @@ impl Notifier {
                 },
                 {
                     "type": "section",
-                    "fields": [
-                        {
-                            "type": "mrkdwn",
-                            "text": format!("*Name:*\n{}", msg.display_name())
-                        },
-                        {
-                            "type": "mrkdwn",
-                            "text": format!("*GPU:*\n{}", msg.gpu_type)
-                        },
-                        {
-                            "type": "mrkdwn",
-                            "text": format!("*Region:*\n{}", msg.region)
-                        },
-                        {
-                            "type": "mrkdwn",
-                            "text": format!("*IP:*\n{}", msg.ip)
-                        }
-                    ]
+                    "fields": fields
                 },
                 {
                     "type": "section",
@@ -200,7 +636,17 @@ impl Notifier {
                     }
                 }
             ]
-        });
+        })
+    }
+
+    /// Send notification to Discord
+    async fn send_discord(&self, config: &DiscordConfig, msg: &InstanceReadyMessage) -> Result<()> {
+        let verbosity = self.config.verbosity;
+        let payload = if self.config.plain {
+            json!({ "content": msg.plain_text_for(verbosity) })
+        } else {
+            Self::discord_embed(msg, verbosity)
+        };
 
         let response = self
             .client
@@ -212,62 +658,31 @@ impl Notifier {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("Slack webhook failed ({}): {}", status, body);
+            anyhow::bail!("Discord webhook failed ({}): {}", status, body);
         }
 
         Ok(())
     }
 
-    /// Send notification to Discord
-    async fn send_discord(&self, config: &DiscordConfig, msg: &InstanceReadyMessage) -> Result<()> {
-        let payload = json!({
+    fn discord_embed(msg: &InstanceReadyMessage, verbosity: Verbosity) -> serde_json::Value {
+        let mut fields: Vec<serde_json::Value> = msg
+            .fields(verbosity)
+            .into_iter()
+            .map(|(label, value)| json!({ "name": label, "value": value, "inline": true }))
+            .collect();
+        fields.push(json!({
+            "name": "SSH Command",
+            "value": format!("```{}```", msg.ssh_command()),
+            "inline": false
+        }));
+
+        json!({
             "embeds": [{
                 "title": "GPU Instance Ready!",
                 "color": 5763719,  // Green color
-                "fields": [
-                    {
-                        "name": "Name",
-                        "value": msg.display_name(),
-                        "inline": true
-                    },
-                    {
-                        "name": "GPU",
-                        "value": msg.gpu_type,
-                        "inline": true
-                    },
-                    {
-                        "name": "Region",
-                        "value": msg.region,
-                        "inline": true
-                    },
-                    {
-                        "name": "IP Address",
-                        "value": msg.ip,
-                        "inline": true
-                    },
-                    {
-                        "name": "SSH Command",
-                        "value": format!("```{}```", msg.ssh_command()),
-                        "inline": false
-                    }
-                ]
+                "fields": fields
             }]
-        });
-
-        let response = self
-            .client
-            .post(&config.webhook_url)
-            .json(&payload)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("Discord webhook failed ({}): {}", status, body);
-        }
-
-        Ok(())
+        })
     }
 
     /// Send notification to Telegram
@@ -281,19 +696,18 @@ impl Notifier {
             config.bot_token
         );
 
-        let text = format!(
-            "*GPU Instance Ready\\!*\n\n\
-             *Name:* `{}`\n\
-             *GPU:* {}\n\
-             *Region:* {}\n\
-             *IP:* `{}`\n\n\
-             *SSH Command:*\n```\n{}\n```",
-            escape_telegram_markdown(msg.display_name()),
-            escape_telegram_markdown(&msg.gpu_type),
-            escape_telegram_markdown(&msg.region),
-            msg.ip,
+        let mut text = String::from("*GPU Instance Ready\\!*\n\n");
+        for (label, value) in msg.fields(self.config.verbosity) {
+            text.push_str(&format!(
+                "*{}:* {}\n",
+                escape_telegram_markdown(label),
+                escape_telegram_markdown(&value)
+            ));
+        }
+        text.push_str(&format!(
+            "\n*SSH Command:*\n```\n{}\n```",
             msg.ssh_command()
-        );
+        ));
 
         let payload = json!({
             "chat_id": config.chat_id,
@@ -313,6 +727,14 @@ impl Notifier {
     }
 }
 
+/// Count the (succeeded, failed) channels in `send_all` results.
+///
+/// `succeeded == 0 && failed > 0` means no channel delivered the message.
+pub fn summarize(results: &[(&str, Result<()>)]) -> (usize, usize) {
+    let succeeded = results.iter().filter(|(_, r)| r.is_ok()).count();
+    (succeeded, results.len() - succeeded)
+}
+
 /// Escape special characters for Telegram MarkdownV2
 fn escape_telegram_markdown(text: &str) -> String {
     let special_chars = [
@@ -347,10 +769,17 @@ mod tests {
             ip: "1.2.3.4".to_string(),
             gpu_type: "gpu_1x_a100".to_string(),
             region: "us-east-1".to_string(),
+            region_description: None,
+            filesystems: Vec::new(),
+            ready_after: None,
         };
 
         assert_eq!(msg.ssh_command(), "ssh ubuntu@1.2.3.4");
         assert_eq!(msg.display_name(), "my-gpu");
+        assert_eq!(
+            msg.plain_text(),
+            "GPU Instance Ready! my-gpu (gpu_1x_a100, us-east-1) at 1.2.3.4. SSH: ssh ubuntu@1.2.3.4"
+        );
 
         let msg_no_name = InstanceReadyMessage {
             instance_id: "abc123".to_string(),
@@ -358,9 +787,218 @@ mod tests {
             ip: "1.2.3.4".to_string(),
             gpu_type: "gpu_1x_a100".to_string(),
             region: "us-east-1".to_string(),
+            region_description: None,
+            filesystems: Vec::new(),
+            ready_after: None,
         };
 
         assert_eq!(msg_no_name.display_name(), "abc123");
+
+        let msg_described = InstanceReadyMessage {
+            region_description: Some("US East (Virginia)".to_string()),
+            ..msg_no_name
+        };
+        assert_eq!(msg_described.region_display(), "US East (Virginia)");
+        assert!(msg_described
+            .plain_text()
+            .contains("(gpu_1x_a100, US East (Virginia))"));
+
+        let labels = |verbosity| {
+            msg_described
+                .fields(verbosity)
+                .into_iter()
+                .map(|(label, _)| label)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(Verbosity::Minimal), ["Name"]);
+        assert_eq!(labels(Verbosity::Normal), ["Name", "GPU", "Region", "IP"]);
+        let detailed = InstanceReadyMessage {
+            filesystems: vec!["data".to_string()],
+            ready_after: Some(Duration::from_secs(134)),
+            ..msg_described
+        };
+        assert_eq!(
+            detailed.plain_text_for(Verbosity::Detailed),
+            "GPU Instance Ready! abc123 (gpu_1x_a100, US East (Virginia)) at 1.2.3.4. SSH: ssh ubuntu@1.2.3.4 \
+             (Instance ID: abc123, Region Code: us-east-1, Filesystems: data, Ready In: 2m14s)"
+        );
+        assert_eq!(
+            detailed.plain_text_for(Verbosity::Minimal),
+            "GPU Instance Ready! abc123. SSH: ssh ubuntu@1.2.3.4"
+        );
+        assert_eq!(Verbosity::parse(" Detailed "), Some(Verbosity::Detailed));
+        assert_eq!(Verbosity::parse("loud"), None);
+    }
+
+    #[test]
+    fn test_channel_status() {
+        let status = ChannelStatus::from_vars(|name| match name {
+            "LAMBDA_NOTIFY_SLACK_WEBHOOK" => {
+                Some("https://hooks.slack.com/services/T/B/secret".into())
+            }
+            "LAMBDA_NOTIFY_TELEGRAM_BOT_TOKEN" => Some("123:secret".into()),
+            _ => None,
+        });
+
+        assert_eq!(status[0].channel, "Slack");
+        assert!(status[0].configured);
+        assert_eq!(status[0].detail, "webhook host hooks.slack.com");
+        assert!(!status[1].configured);
+        assert_eq!(status[1].detail, "LAMBDA_NOTIFY_DISCORD_WEBHOOK not set");
+        assert!(!status[2].configured);
+        assert_eq!(status[2].detail, "bot token set, chat ID missing");
+        assert!(status.iter().all(|s| !s.detail.contains("secret")));
+    }
+
+    #[test]
+    fn test_capacity_change_message() {
+        let regions = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        let msg = CapacityChangeMessage {
+            gpu_type: "gpu_8x_h100".to_string(),
+            before: regions(&["us-east-1", "us-west-2"]),
+            after: regions(&["us-west-2", "asia-south-1"]),
+        };
+        assert_eq!(
+            msg.plain_text(),
+            "gpu_8x_h100 capacity changed.\nNow: us-west-2, asia-south-1\nWas: us-east-1, us-west-2\nAppeared: asia-south-1\nGone: us-east-1"
+        );
+
+        let appeared = CapacityChangeMessage {
+            gpu_type: "gpu_1x_a10".to_string(),
+            before: Vec::new(),
+            after: regions(&["us-east-1"]),
+        };
+        assert_eq!(
+            appeared.plain_text(),
+            "gpu_1x_a10 capacity changed.\nNow: us-east-1\nWas: none\nAppeared: us-east-1"
+        );
+    }
+
+    #[test]
+    fn test_batch_tracker() {
+        let ready = |id: &str| InstanceReadyMessage {
+            instance_id: id.to_string(),
+            instance_name: None,
+            ip: "1.2.3.4".to_string(),
+            gpu_type: "gpu_1x_a10".to_string(),
+            region: "us-east-1".to_string(),
+            region_description: None,
+            filesystems: Vec::new(),
+            ready_after: None,
+        };
+
+        let mut batch = BatchTracker::new(vec!["i-1".to_string(), "i-2".to_string()]);
+        assert!(!batch.record_ready(ready("i-1")));
+        assert!(!batch.record_ready(ready("i-1")));
+        assert!(!batch.record_ready(ready("i-9")));
+        assert_eq!(
+            batch.message().plain_text(),
+            "1 of 2 GPU instances ready.\n- i-1 (gpu_1x_a10, us-east-1): ssh ubuntu@1.2.3.4\nNot ready: i-2"
+        );
+
+        assert!(batch.record_ready(ready("i-2")));
+        let message = batch.message();
+        assert!(message.pending.is_empty());
+        assert!(message
+            .plain_text()
+            .starts_with("All 2 GPU instances ready!"));
+    }
+
+    #[test]
+    fn test_mark_notified_dedup() {
+        assert!(mark_notified("dedup-test-1"));
+        assert!(!mark_notified("dedup-test-1"));
+        assert!(mark_notified("dedup-test-2"));
+    }
+
+    fn ready_message(instance_id: &str) -> InstanceReadyMessage {
+        InstanceReadyMessage {
+            instance_id: instance_id.to_string(),
+            instance_name: None,
+            ip: "10.0.0.1".to_string(),
+            gpu_type: "gpu_1x_a10".to_string(),
+            region: "us-east-1".to_string(),
+            region_description: None,
+            filesystems: Vec::new(),
+            ready_after: None,
+        }
+    }
+
+    fn slack_notifier(webhook_url: String) -> Notifier {
+        Notifier::new(NotifyConfig {
+            slack: Some(SlackConfig { webhook_url }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_failed_delivery_is_not_marked() {
+        // Nothing listens on port 1, so every send fails
+        let notifier = slack_notifier("http://127.0.0.1:1/".to_string());
+        let msg = ready_message("undelivered-test");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let results = rt.block_on(notifier.send_all(&msg));
+        assert_eq!(summarize(&results), (0, 1));
+        let batch = BatchReadyMessage {
+            ready: vec![msg],
+            pending: Vec::new(),
+        };
+        assert_eq!(summarize(&rt.block_on(notifier.send_batch(&batch))), (0, 1));
+        // Both failures released the claim
+        assert!(mark_notified("undelivered-test"));
+        assert!(rt.block_on(notifier.send_all(&batch.ready[0])).is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_sends_deliver_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let delivered = Arc::new(AtomicUsize::new(0));
+        let listener = rt
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let counter = Arc::clone(&delivered);
+        // A webhook that accepts every message, after a pause so the sends overlap
+        rt.spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = [0; 4096];
+                    let _ = stream.read(&mut buf).await;
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    let _ = stream
+                        .write_all(
+                            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        )
+                        .await;
+                });
+            }
+        });
+
+        let notifier = slack_notifier(url);
+        let msg = ready_message("concurrent-test");
+        let (first, second) =
+            rt.block_on(async { tokio::join!(notifier.send_all(&msg), notifier.send_all(&msg)) });
+        assert_eq!(first.len() + second.len(), 1);
+        assert_eq!(delivered.load(Ordering::SeqCst), 1);
+        assert!(rt.block_on(notifier.send_all(&msg)).is_empty());
+    }
+
+    #[test]
+    fn test_summarize() {
+        let results = vec![
+            ("Slack", Ok(())),
+            ("Discord", Err(anyhow::anyhow!("boom"))),
+            ("Telegram", Ok(())),
+        ];
+        assert_eq!(summarize(&results), (2, 1));
+        assert_eq!(summarize(&[]), (0, 0));
     }
 
     #[test]
@@ -0,0 +1,114 @@
+//! Opt-in local log of instance-type prices.
+//!
+//! When `LAMBDA_PRICE_HISTORY` is set to a file path, each `lambda list` appends
+//! one JSON line per instance type; `lambda price-history` reads them back.
+
+use crate::api::InstanceTypeData;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One recorded price for an instance type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceSnapshot {
+    /// RFC 3339 time the price was seen
+    pub timestamp: String,
+    #[serde(rename = "type")]
+    pub instance_type: String,
+    pub price_cents: i32,
+}
+
+/// Price log path from `LAMBDA_PRICE_HISTORY`, if recording is enabled
+pub fn path_from_env() -> Option<PathBuf> {
+    std::env::var("LAMBDA_PRICE_HISTORY")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Append the current price of every type in `types` to the log at `path`
+pub fn append_snapshots(path: &Path, types: &[InstanceTypeData], timestamp: &str) -> Result<()> {
+    let mut lines = String::new();
+    for t in types {
+        let snapshot = PriceSnapshot {
+            timestamp: timestamp.to_string(),
+            instance_type: t.name.clone(),
+            price_cents: t.price_cents_per_hour,
+        };
+        lines.push_str(&serde_json::to_string(&snapshot)?);
+        lines.push('\n');
+    }
+
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .with_context(|| format!("Failed to write price history to {}", path.display()))
+}
+
+/// Read the recorded prices for `gpu`, oldest first
+pub fn read_history(path: &Path, gpu: &str) -> Result<Vec<PriceSnapshot>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read price history from {}", path.display()))?;
+    Ok(parse_history(&contents, gpu))
+}
+
+/// Parse log lines for `gpu`, skipping any that are malformed (e.g. a torn write)
+fn parse_history(contents: &str, gpu: &str) -> Vec<PriceSnapshot> {
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<PriceSnapshot>(line).ok())
+        .filter(|s| s.instance_type == gpu)
+        .collect()
+}
+
+/// Render prices as a one-line sparkline, scaled between their min and max
+pub fn sparkline(prices: &[i32]) -> String {
+    let (Some(&min), Some(&max)) = (prices.iter().min(), prices.iter().max()) else {
+        return String::new();
+    };
+    let range = (max - min).max(1) as f64;
+
+    prices
+        .iter()
+        .map(|&p| {
+            let level = ((p - min) as f64 / range * (SPARK_CHARS.len() - 1) as f64).round();
+            SPARK_CHARS[level as usize]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_history() {
+        let contents = concat!(
+            r#"{"timestamp":"2025-01-01T00:00:00Z","type":"gpu_1x_a10","price_cents":75}"#,
+            "\n",
+            r#"{"timestamp":"2025-01-01T00:00:00Z","type":"gpu_1x_h100","price_cents":249}"#,
+            "\n",
+            r#"{"timestamp":"2025-01-02T00:00:00Z","type":"gpu_1x_a10","pri"#,
+            "\n",
+            r#"{"timestamp":"2025-01-03T00:00:00Z","type":"gpu_1x_a10","price_cents":80}"#,
+            "\n",
+        );
+
+        let history = parse_history(contents, "gpu_1x_a10");
+        let prices: Vec<i32> = history.iter().map(|s| s.price_cents).collect();
+        assert_eq!(prices, vec![75, 80]);
+        assert_eq!(history[1].timestamp, "2025-01-03T00:00:00Z");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[100, 100]), "▁▁");
+        assert_eq!(sparkline(&[0, 50, 100]), "▁▅█");
+    }
+}
@@ -0,0 +1,151 @@
+//! Retry budget shared by every request made through one client.
+//!
+//! Each retry draws one attempt and its backoff delay from the budget; once
+//! either runs out, failures are returned immediately. This bounds how long a
+//! single operation can spend retrying, however many requests it makes.
+//! Long-running callers (MCP tool calls, `find` checks, metrics scrapes) take a
+//! `LambdaClient::for_operation` view per operation, each with its own budget.
+//!
+//! Configure via `LAMBDA_RETRY_BUDGET=<attempts>[,<seconds>]` (e.g. `10,60`);
+//! `0` disables retries.
+
+use anyhow::{anyhow, Result};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 10;
+pub const DEFAULT_RETRY_SECS: u64 = 60;
+
+/// First backoff delay; doubles with each retry up to `MAX_BACKOFF`
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+#[derive(Debug, Default)]
+struct Spent {
+    attempts: u32,
+    delay: Duration,
+}
+
+/// Bounded pool of retries (attempt count and total backoff time)
+#[derive(Debug)]
+pub struct RetryBudget {
+    max_attempts: u32,
+    max_delay: Duration,
+    spent: Mutex<Spent>,
+}
+
+impl Default for RetryBudget {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_RETRY_ATTEMPTS,
+            Duration::from_secs(DEFAULT_RETRY_SECS),
+        )
+    }
+}
+
+impl RetryBudget {
+    pub fn new(max_attempts: u32, max_delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            max_delay,
+            spent: Mutex::new(Spent::default()),
+        }
+    }
+
+    /// Parse a `<attempts>[,<seconds>]` budget (as in `LAMBDA_RETRY_BUDGET`)
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (attempts, secs) = match spec.split_once(',') {
+            Some((attempts, secs)) => (attempts, Some(secs)),
+            None => (spec, None),
+        };
+
+        let invalid = || {
+            anyhow!(
+                "Invalid retry budget '{}' (expected attempts[,seconds])",
+                spec
+            )
+        };
+        let attempts = attempts.trim().parse().map_err(|_| invalid())?;
+        let secs = match secs {
+            Some(secs) => secs.trim().parse().map_err(|_| invalid())?,
+            None => DEFAULT_RETRY_SECS,
+        };
+
+        Ok(Self::new(attempts, Duration::from_secs(secs)))
+    }
+
+    /// Take one retry from the budget, returning how long to wait before it.
+    ///
+    /// `requested` overrides the exponential backoff (e.g. from `Retry-After`).
+    /// Returns `None` once the attempts or the time are used up.
+    pub fn acquire(&self, requested: Option<Duration>) -> Option<Duration> {
+        let mut spent = self.spent.lock().ok()?;
+        if spent.attempts >= self.max_attempts {
+            return None;
+        }
+
+        let delay = requested.unwrap_or_else(|| backoff(spent.attempts));
+        if spent.delay + delay > self.max_delay {
+            return None;
+        }
+
+        spent.attempts += 1;
+        spent.delay += delay;
+        Some(delay)
+    }
+
+    /// A budget with the same limits and nothing spent, for a new operation
+    pub fn fresh(&self) -> Self {
+        Self::new(self.max_attempts, self.max_delay)
+    }
+}
+
+fn backoff(retry: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(retry))
+        .min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_limits_attempts_and_time() {
+        let budget = RetryBudget::new(3, Duration::from_secs(60));
+        assert_eq!(budget.acquire(None), Some(Duration::from_millis(500)));
+        assert_eq!(budget.acquire(None), Some(Duration::from_secs(1)));
+        assert_eq!(budget.acquire(None), Some(Duration::from_secs(2)));
+        assert_eq!(budget.acquire(None), None);
+
+        let fresh = budget.fresh();
+        assert_eq!(budget.acquire(None), None);
+        assert_eq!(
+            fresh.acquire(Some(Duration::from_secs(30))),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(fresh.acquire(Some(Duration::from_secs(31))), None);
+        assert_eq!(
+            fresh.acquire(Some(Duration::from_secs(30))),
+            Some(Duration::from_secs(30))
+        );
+
+        let disabled = RetryBudget::new(0, Duration::from_secs(60));
+        assert_eq!(disabled.acquire(None), None);
+    }
+
+    #[test]
+    fn test_parse_retry_budget() {
+        let budget = RetryBudget::parse("5").unwrap();
+        assert_eq!(budget.max_attempts, 5);
+        assert_eq!(budget.max_delay, Duration::from_secs(DEFAULT_RETRY_SECS));
+
+        let budget = RetryBudget::parse("3, 20").unwrap();
+        assert_eq!(budget.max_attempts, 3);
+        assert_eq!(budget.max_delay, Duration::from_secs(20));
+
+        assert!(RetryBudget::parse("").is_err());
+        assert!(RetryBudget::parse("5,soon").is_err());
+        assert!(RetryBudget::parse("-1").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! The environment settings in effect, for `lambda config`.
+//!
+//! Secrets (API keys, commands that print them, webhook URLs, header values)
+//! are reported only as set or unset.
+
+use serde::Serialize;
+
+/// How a setting's value may be shown
+enum Display {
+    /// Shown as is, with the value used when unset
+    Plain(&'static str),
+    /// Only whether it is set
+    Secret,
+    /// `name=value` pairs, shown by name only
+    HeaderNames,
+}
+
+/// Every variable the CLI or MCP server reads, in the order the README introduces them
+const SETTINGS: &[(&str, Display)] = &[
+    ("LAMBDA_API_KEY", Display::Secret),
+    ("LAMBDA_API_KEY_COMMAND", Display::Secret),
+    ("LAMBDA_API_KEY_TTL", Display::Plain("never re-fetched")),
+    ("LAMBDA_EXTRA_HEADERS", Display::HeaderNames),
+    ("LAMBDA_PROXY_AUTH_COMMAND", Display::Secret),
+    (
+        "LAMBDA_PROXY_AUTH_HEADER",
+        Display::Plain("Proxy-Authorization"),
+    ),
+    ("LAMBDA_PROXY_AUTH_TTL", Display::Plain("never re-run")),
+    ("LAMBDA_RETRY_BUDGET", Display::Plain("10,60")),
+    ("LAMBDA_NOTIFY_SLACK_WEBHOOK", Display::Secret),
+    ("LAMBDA_NOTIFY_DISCORD_WEBHOOK", Display::Secret),
+    ("LAMBDA_NOTIFY_TELEGRAM_BOT_TOKEN", Display::Secret),
+    ("LAMBDA_NOTIFY_TELEGRAM_CHAT_ID", Display::Plain("unset")),
+    ("LAMBDA_NOTIFY_PLAIN", Display::Plain("false")),
+    ("LAMBDA_NOTIFY_VERBOSITY", Display::Plain("normal")),
+    ("LAMBDA_PRICE_HISTORY", Display::Plain("off")),
+    ("LAMBDA_GPU_ALIASES", Display::Plain("none")),
+    ("LAMBDA_REQUIRE_NAME", Display::Plain("false")),
+    ("LAMBDA_BOOTING_WARNING_SECS", Display::Plain("180")),
+    ("LAMBDA_NO_DOTENV", Display::Plain("false")),
+    ("LAMBDA_CONFIG_JSON", Display::Secret),
+    ("LAMBDA_MCP_CACHE_TTL", Display::Plain("5")),
+    ("LAMBDA_NOTIFY_MAX_WAIT", Display::Plain("600")),
+];
+
+/// One setting and the value it currently has
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Setting {
+    pub name: &'static str,
+    /// The value in effect, or `(redacted)` for a secret that is set
+    pub value: String,
+    /// `env` if the variable is set, otherwise `default`
+    pub source: &'static str,
+}
+
+impl Setting {
+    /// All settings, from the process environment
+    pub fn from_env() -> Vec<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// All settings, reading variables through `var`
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Vec<Self> {
+        SETTINGS
+            .iter()
+            .map(|(name, display)| {
+                let set = var(name).filter(|v| !v.is_empty());
+                let source = if set.is_some() { "env" } else { "default" };
+                let value = match (display, set) {
+                    (Display::Plain(default), None) => default.to_string(),
+                    (_, None) => "unset".to_string(),
+                    (Display::Plain(_), Some(value)) => value,
+                    (Display::Secret, Some(_)) => "(redacted)".to_string(),
+                    (Display::HeaderNames, Some(headers)) => headers
+                        .split(',')
+                        .filter_map(|pair| pair.split_once('=').map(|(n, _)| n.trim()))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                };
+                Setting {
+                    name,
+                    value,
+                    source,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Where the API key comes from, following `LambdaClient::from_env`'s precedence
+pub fn api_key_source(var: impl Fn(&str) -> Option<String>) -> Option<&'static str> {
+    ["LAMBDA_API_KEY", "LAMBDA_API_KEY_COMMAND"]
+        .into_iter()
+        .find(|name| var(name).is_some_and(|v| !v.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::{DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_SECS};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_settings_redact_secrets() {
+        let vars: HashMap<&str, &str> = [
+            ("LAMBDA_API_KEY", "secret_abc"),
+            (
+                "LAMBDA_EXTRA_HEADERS",
+                "X-Request-Id=42,X-Proxy-Token=abc123",
+            ),
+            ("LAMBDA_NOTIFY_VERBOSITY", "detailed"),
+        ]
+        .into();
+        let settings = Setting::from_vars(|name| vars.get(name).map(|v| v.to_string()));
+        let get = |name| settings.iter().find(|s| s.name == name).unwrap();
+
+        assert_eq!(get("LAMBDA_API_KEY").value, "(redacted)");
+        assert_eq!(get("LAMBDA_API_KEY").source, "env");
+        assert_eq!(get("LAMBDA_API_KEY_COMMAND").value, "unset");
+        assert_eq!(
+            get("LAMBDA_EXTRA_HEADERS").value,
+            "X-Request-Id, X-Proxy-Token"
+        );
+        assert_eq!(get("LAMBDA_NOTIFY_VERBOSITY").value, "detailed");
+        assert_eq!(
+            get("LAMBDA_RETRY_BUDGET").value,
+            format!("{},{}", DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_SECS)
+        );
+        assert_eq!(get("LAMBDA_RETRY_BUDGET").source, "default");
+        assert!(!settings.iter().any(|s| s.value.contains("secret_abc")));
+
+        let key_source = api_key_source(|name| vars.get(name).map(|v| v.to_string()));
+        assert_eq!(key_source, Some("LAMBDA_API_KEY"));
+        assert_eq!(api_key_source(|_| None), None);
+    }
+}
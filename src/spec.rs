@@ -0,0 +1,222 @@
+//! Fleet spec files: a JSON snapshot of running instances that can be relaunched.
+//!
+//! `lambda export-instances` writes one; `lambda import-instances --file` reads
+//! it back and launches each entry. While an import runs, its progress is kept
+//! next to the spec (see [`ImportProgress`]) so an interrupted import can resume.
+
+use crate::api::{Instance, LaunchOptions};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A set of instances to launch
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FleetSpec {
+    pub instances: Vec<InstanceSpec>,
+}
+
+/// Everything needed to relaunch one instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceSpec {
+    pub instance_type: String,
+    /// Region to launch in (auto-selected if not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    pub ssh_key_names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_system_names: Vec<String>,
+}
+
+/// An instance left out of an export, and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedInstance {
+    pub id: String,
+    pub reason: &'static str,
+}
+
+impl InstanceSpec {
+    /// Capture an instance's launch settings, or why it can't be relaunched
+    pub fn from_instance(instance: &Instance) -> Result<Self, &'static str> {
+        let instance_type = instance.type_name().ok_or("no instance type")?;
+        let ssh_key_names = instance.ssh_key_names.clone().unwrap_or_default();
+        if ssh_key_names.is_empty() {
+            return Err("no SSH keys");
+        }
+
+        Ok(Self {
+            instance_type: instance_type.to_string(),
+            region: instance.region.as_ref().and_then(|r| r.name.clone()),
+            ssh_key_names,
+            name: instance.name.clone(),
+            file_system_names: instance.file_system_names.clone().unwrap_or_default(),
+        })
+    }
+
+    /// SSH key to launch with (the API accepts one key per launch)
+    pub fn ssh_key(&self) -> Option<&str> {
+        self.ssh_key_names.first().map(String::as_str)
+    }
+
+    /// SSH keys after the first, which a launch from this spec leaves off
+    pub fn dropped_ssh_keys(&self) -> &[String] {
+        self.ssh_key_names.get(1..).unwrap_or_default()
+    }
+
+    pub fn launch_options(&self) -> LaunchOptions {
+        LaunchOptions {
+            name: self.name.clone(),
+            region: self.region.clone(),
+            filesystem: self.file_system_names.first().cloned(),
+            ..LaunchOptions::default()
+        }
+    }
+}
+
+impl FleetSpec {
+    /// Build a spec from running instances, skipping any that can't be relaunched
+    pub fn from_instances(instances: &[Instance]) -> (Self, Vec<SkippedInstance>) {
+        let mut spec = Self::default();
+        let mut skipped = Vec::new();
+
+        for instance in instances {
+            match InstanceSpec::from_instance(instance) {
+                Ok(entry) => spec.instances.push(entry),
+                Err(reason) => skipped.push(SkippedInstance {
+                    id: instance.id.clone().unwrap_or_else(|| "N/A".to_string()),
+                    reason,
+                }),
+            }
+        }
+
+        (spec, skipped)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read fleet spec from {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid fleet spec in {}", path.display()))
+    }
+}
+
+/// Which spec entries an import has launched so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportProgress {
+    /// Number of entries in the spec
+    pub intended: usize,
+    /// Instance ID launched for each spec entry, by index
+    pub launched: BTreeMap<usize, String>,
+}
+
+impl ImportProgress {
+    /// Progress file for the spec at `spec_path` (e.g. `fleet.json.progress`)
+    pub fn path_for(spec_path: &Path) -> PathBuf {
+        let mut path = spec_path.as_os_str().to_owned();
+        path.push(".progress");
+        PathBuf::from(path)
+    }
+
+    /// Load saved progress, if an earlier import left any
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map(Some)
+                .with_context(|| format!("Invalid import progress in {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+                .with_context(|| format!("Failed to read import progress from {}", path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write import progress to {}", path.display()))
+    }
+
+    pub fn is_launched(&self, index: usize) -> bool {
+        self.launched.contains_key(&index)
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.intended.saturating_sub(self.launched.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_instances_skips_incomplete() {
+        let instances: Vec<Instance> = serde_json::from_value(serde_json::json!([
+            {
+                "id": "i-1",
+                "name": "train",
+                "instance_type": { "name": "gpu_1x_a10" },
+                "region": { "name": "us-east-1" },
+                "ssh_key_names": ["alice"],
+                "file_system_names": ["data"]
+            },
+            { "id": "i-2", "ssh_key_names": ["alice"] },
+            { "id": "i-3", "instance_type": { "name": "gpu_1x_a10" } }
+        ]))
+        .unwrap();
+
+        let (spec, skipped) = FleetSpec::from_instances(&instances);
+        assert_eq!(spec.instances.len(), 1);
+        assert_eq!(
+            skipped,
+            vec![
+                SkippedInstance {
+                    id: "i-2".to_string(),
+                    reason: "no instance type"
+                },
+                SkippedInstance {
+                    id: "i-3".to_string(),
+                    reason: "no SSH keys"
+                },
+            ]
+        );
+
+        let entry = &spec.instances[0];
+        assert_eq!(entry.ssh_key(), Some("alice"));
+        assert!(entry.dropped_ssh_keys().is_empty());
+        let shared = InstanceSpec {
+            ssh_key_names: vec!["alice".to_string(), "bob".to_string()],
+            ..entry.clone()
+        };
+        assert_eq!(shared.dropped_ssh_keys(), ["bob".to_string()]);
+        let options = entry.launch_options();
+        assert_eq!(options.region.as_deref(), Some("us-east-1"));
+        assert_eq!(options.filesystem.as_deref(), Some("data"));
+
+        let json = serde_json::to_string(&spec).unwrap();
+        assert_eq!(serde_json::from_str::<FleetSpec>(&json).unwrap(), spec);
+    }
+
+    #[test]
+    fn test_import_progress() {
+        assert_eq!(
+            ImportProgress::path_for(Path::new("dir/fleet.json")),
+            PathBuf::from("dir/fleet.json.progress")
+        );
+
+        let mut progress = ImportProgress {
+            intended: 3,
+            ..ImportProgress::default()
+        };
+        progress.launched.insert(1, "i-2".to_string());
+        assert!(progress.is_launched(1));
+        assert!(!progress.is_launched(0));
+        assert_eq!(progress.remaining(), 2);
+
+        let json = serde_json::to_string(&progress).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ImportProgress>(&json).unwrap(),
+            progress
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Placeholder templates for `lambda running --template`.
+//!
+//! `{field}` is replaced with an instance field; `{{` and `}}` are literal braces.
+
+use crate::api::Instance;
+use anyhow::{anyhow, Result};
+
+/// Placeholders a template may use
+pub const FIELDS: [&str; 7] = ["id", "name", "ip", "status", "type", "region", "ssh_keys"];
+
+/// One piece of a parsed template
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Field(&'static str),
+}
+
+/// A validated template, ready to render any number of instances
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parse `template`, rejecting unknown placeholders and unbalanced braces
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => {
+                                return Err(anyhow!("Unclosed '{{' in template '{}'", template))
+                            }
+                        }
+                    }
+                    let field = FIELDS.iter().find(|&&f| f == name).ok_or_else(|| {
+                        anyhow!(
+                            "Unknown placeholder '{{{}}}'. Available: {}",
+                            name,
+                            FIELDS.map(|f| format!("{{{}}}", f)).join(", ")
+                        )
+                    })?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => return Err(anyhow!("Unmatched '}}' in template '{}'", template)),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Render one instance; missing values render as empty strings
+    pub fn render(&self, instance: &Instance) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.clone(),
+                Part::Field(field) => field_value(instance, field),
+            })
+            .collect()
+    }
+}
+
+fn field_value(instance: &Instance, field: &str) -> String {
+    let value = match field {
+        "id" => instance.id.clone(),
+        "name" => instance.name.clone(),
+        "ip" => instance.ip.clone(),
+        "status" => Some(instance.status_display()),
+        "type" => instance.type_name().map(String::from),
+        "region" => instance.region.as_ref().and_then(|r| r.name.clone()),
+        "ssh_keys" => instance.ssh_key_names.as_ref().map(|keys| keys.join(",")),
+        _ => None,
+    };
+    value.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let instance: Instance = serde_json::from_value(serde_json::json!({
+            "id": "i-1",
+            "status": "active",
+            "ip": "1.2.3.4",
+            "instance_type": { "name": "gpu_1x_a10" },
+            "ssh_key_names": ["alice", "bob"]
+        }))
+        .unwrap();
+
+        let template =
+            Template::parse("{id} {ip} {status} [{name}] {type} {ssh_keys} {{x}}").unwrap();
+        assert_eq!(
+            template.render(&instance),
+            "i-1 1.2.3.4 active [] gpu_1x_a10 alice,bob {x}"
+        );
+
+        let err = Template::parse("{id} {uptime}").unwrap_err().to_string();
+        assert!(err.starts_with("Unknown placeholder '{uptime}'"));
+        assert!(Template::parse("{id").is_err());
+        assert!(Template::parse("{id} {id").is_err());
+        assert!(Template::parse("id}").is_err());
+    }
+}